@@ -0,0 +1,88 @@
+//! Hosts online games for othello-rs. Clients play on it through a server
+//! profile pointing at `http://<this machine>:<port>`.
+
+use std::{env, process, time::Duration};
+
+use othello_rs::server;
+use tokio::net::TcpListener;
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    bind: String,
+    port: u16,
+    grace: Duration,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0".to_string(),
+            port: 3000,
+            grace: server::RECONNECT_GRACE,
+        }
+    }
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bind" => parsed.bind = args.next().ok_or("--bind needs an address")?,
+                "--port" => {
+                    parsed.port = args
+                        .next()
+                        .and_then(|p| p.parse().ok())
+                        .ok_or("--port needs a port number")?;
+                }
+                "--grace" => {
+                    let secs = args
+                        .next()
+                        .and_then(|s| s.parse().ok())
+                        .ok_or("--grace needs a number of seconds")?;
+                    parsed.grace = Duration::from_secs(secs);
+                }
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+const USAGE: &str = "usage: othello-server [--bind <address>] [--port <port>] [--grace <seconds>]";
+
+fn main() {
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        let listener = TcpListener::bind((args.bind.as_str(), args.port))
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("unable to listen on {}:{}: {}", args.bind, args.port, e);
+                process::exit(1);
+            });
+        println!("serving games on http://{}", listener.local_addr().unwrap());
+        server::serve(listener, args.grace, false).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_flags() {
+        let args = |a: &[&str]| Args::parse(a.iter().map(|s| s.to_string()));
+        assert_eq!(args(&[]).unwrap(), Args::default());
+        let parsed = args(&["--port", "8080", "--bind", "127.0.0.1", "--grace", "5"]).unwrap();
+        assert_eq!(parsed.port, 8080);
+        assert_eq!(parsed.bind, "127.0.0.1");
+        assert_eq!(parsed.grace, Duration::from_secs(5));
+        assert!(args(&["--port", "http"]).is_err());
+        assert!(args(&["--verbose"]).is_err());
+    }
+}
@@ -1,14 +1,23 @@
-use crossterm::style::Color;
+use crossterm::{
+    event::KeyCode,
+    style::{Attribute, Color},
+};
 
-use crate::termin::{
-    buffer::Rect,
-    elements::{Element, Rectangle, Text},
-    window::Position,
+use crate::{
+    game::board::{Side, BLACK, WHITE},
+    termin::{
+        buffer::Rect,
+        elements::{Element, InputWindow, Rectangle, Text},
+        terminal_window::TerminalHandler,
+        window::{draw_elements, Position, Window, WindowRef},
+    },
+    theme::Theme,
 };
 
 pub struct DialogBox {
     text_box: Text,
     rect: Rectangle,
+    theme: Theme,
 }
 
 impl DialogBox {
@@ -16,6 +25,7 @@ impl DialogBox {
         Self {
             text_box: Text::default().size(width - 2, height - 2).xy(1, 1),
             rect: Rectangle::default().size(width, height),
+            theme: Theme::load(),
         }
     }
 
@@ -65,12 +75,12 @@ impl DialogBox {
     }
 
     pub fn error(&mut self, text: &str) {
-        self.rect.set_bg(Color::Red);
+        self.rect.set_bg(self.theme.error);
         self.set_text(text);
     }
 
     pub fn info(&mut self, info: &str) {
-        self.rect.set_bg(Color::Blue);
+        self.rect.set_bg(self.theme.info);
         self.set_text(info);
     }
 }
@@ -81,3 +91,274 @@ impl Element for DialogBox {
         self.text_box.draw(buf);
     }
 }
+
+/// A vertical list of lines with one highlighted, scrolled so the
+/// highlighted line stays visible.
+pub struct ListBox {
+    rect: Rect,
+    items: Vec<String>,
+    cursor: usize,
+    top: usize,
+    theme: Theme,
+}
+
+impl ListBox {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            rect: Rect::new(0, 0, width, height),
+            items: vec![],
+            cursor: 0,
+            top: 0,
+            theme: Theme::load(),
+        }
+    }
+
+    pub fn xy(mut self, x: u32, y: u32) -> Self {
+        self.rect.x = x;
+        self.rect.y = y;
+        self
+    }
+
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        self.cursor = 0;
+        self.top = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn move_cursor(&mut self, d: i32) {
+        if self.items.is_empty() {
+            return;
+        }
+        let last = self.items.len() as i32 - 1;
+        self.cursor = (self.cursor as i32 + d).clamp(0, last) as usize;
+
+        let height = self.rect.height as usize;
+        if self.cursor < self.top {
+            self.top = self.cursor;
+        } else if self.cursor >= self.top + height {
+            self.top = self.cursor + 1 - height;
+        }
+    }
+}
+
+impl Element for ListBox {
+    fn draw(&self, buf: &mut crate::termin::buffer::Buffer) {
+        let mut line = Text::default().size(self.rect.width, 1);
+
+        for (idx, item) in self
+            .items
+            .iter()
+            .enumerate()
+            .skip(self.top)
+            .take(self.rect.height as usize)
+        {
+            line.set_xy(self.rect.x, self.rect.y + (idx - self.top) as u32);
+            line.set_text(item);
+            if idx == self.cursor {
+                Rectangle::from_rect(line.rect())
+                    .bg(self.theme.frame)
+                    .draw(buf);
+                line.set_fg(self.theme.frame_text);
+            } else {
+                line.set_fg(Color::Reset);
+            }
+            line.draw(buf);
+        }
+    }
+}
+
+/// Shows a single input box in the root window and returns what was typed.
+pub fn prompt(
+    terminal: &mut TerminalHandler,
+    heading: &str,
+    label: &str,
+    width: u32,
+    max_len: i32,
+) -> String {
+    terminal.root.clear();
+    terminal.handle_input(|handler, root| -> String {
+        let label = Text::default().text(label).xy(0, 2);
+        let mut input_win = root.new_child(
+            Window::default()
+                .size(label.width() + width + 2, 10)
+                .xy(2, 2),
+        );
+        let mut input = InputWindow::from(
+            &mut input_win,
+            Window::default()
+                .xy(label.x() + label.width(), label.y())
+                .size(width, (max_len as u32 / width + 1).min(6)),
+        )
+        .start_text((0, 0))
+        .max_len(max_len);
+
+        input_win.draw_element(&label);
+        input_win.draw_text(heading, Position::CenterH);
+        input_win.render();
+
+        handler.draw_window(root).unwrap();
+
+        let text = input.read_string(handler);
+        input_win.delete();
+        text
+    })
+}
+
+/// Shows `dbox` in the root window until a key is pressed.
+pub fn show_dialog(terminal: &mut TerminalHandler, dbox: &DialogBox) {
+    terminal.root.clear();
+    terminal.root.draw_element(dbox);
+    terminal.refresh().unwrap();
+    terminal.getch();
+}
+
+/// Small menu drawn over `parent`. Returns the chosen item, or `None` on Esc.
+pub fn overlay_menu(
+    terminal: &mut TerminalHandler,
+    parent: &mut WindowRef,
+    heading: &str,
+    items: &[&str],
+) -> Option<usize> {
+    let height = items.len() as u32 + 4;
+    let mut border = parent.new_child(
+        Window::default()
+            .bg(Theme::load().frame)
+            .size(28, height + 2)
+            .xy(5, 6),
+    );
+    let mut inner = border.new_child(Window::default().size(24, height).xy(2, 1));
+    let mut list = ListBox::new(20, items.len() as u32).xy(2, 2);
+    list.set_items(items.iter().map(|s| s.to_string()).collect());
+
+    let choice = loop {
+        inner.clear();
+        inner.draw_text(heading, Position::CenterH);
+        inner.draw_element(&list);
+        inner.render_to_parent();
+        border.render();
+        terminal.refresh().unwrap();
+
+        match terminal.getch() {
+            KeyCode::Up => list.move_cursor(-1),
+            KeyCode::Down => list.move_cursor(1),
+            KeyCode::Enter => break Some(list.cursor()),
+            KeyCode::Esc => break None,
+            _ => (),
+        }
+    };
+    border.delete();
+    choice
+}
+
+/// Lets the player pick white or black with the arrows and enter. `None`
+/// when they back out with Esc.
+pub fn choose_side(terminal: &mut TerminalHandler, heading: &str, label: &str) -> Option<Side> {
+    terminal.clear();
+    let theme = Theme::load();
+    let mut win = terminal
+        .root
+        .new_child(Window::default().size(30, 10).bg(theme.frame).xy(2, 2));
+    let rect = win.rect();
+    let mut cur_side = WHITE;
+    let mut white = Text::default()
+        .text("white")
+        .position(rect, Position::CenterB)
+        .attr(Attribute::Underlined)
+        .fg(theme.frame_text);
+    let mut black = Text::default()
+        .text("black")
+        .position(rect, Position::CenterB)
+        .fg(theme.frame_text);
+
+    black.set_xy_rel(black.width() as i16, 1);
+    white.set_xy_rel(-(white.width() as i16), 1);
+
+    draw_elements!(
+        win,
+        Text::default()
+            .text(heading)
+            .fg(theme.frame_text)
+            .attr(Attribute::Bold)
+            .position(rect, Position::CenterH)
+            .xy_rel(0, 1),
+        Text::default()
+            .text(label)
+            .xy_rel(2, 4)
+            .fg(theme.frame_text),
+        white,
+        black
+    );
+
+    win.render();
+    terminal.refresh().unwrap();
+
+    let chosen = loop {
+        match terminal.getch() {
+            KeyCode::Left | KeyCode::Right => {
+                let (picked, other) = if cur_side == WHITE {
+                    cur_side = BLACK;
+                    (&mut black, &mut white)
+                } else {
+                    cur_side = WHITE;
+                    (&mut white, &mut black)
+                };
+                picked.set_attr(Attribute::Underlined);
+                other.set_attr(Attribute::Reset);
+                draw_elements!(win, white, black);
+                terminal.handler.draw_window(&win).unwrap();
+                terminal.flush().unwrap();
+            }
+            KeyCode::Enter => break Some(cur_side),
+            KeyCode::Esc => break None,
+            _ => (),
+        }
+    };
+    win.delete();
+    chosen
+}
+
+/// Full screen list with a heading and a help line, used by the browser screens.
+pub struct ListScreen {
+    pub win: WindowRef,
+    pub list: ListBox,
+}
+
+impl ListScreen {
+    pub fn new(terminal: &mut TerminalHandler) -> Self {
+        let (width, height) = (terminal.root.width(), terminal.root.height());
+        Self {
+            win: terminal
+                .root
+                .new_child(Window::default().size(width, height)),
+            list: ListBox::new(width - 4, height - 6).xy(2, 4),
+        }
+    }
+
+    pub fn render(&mut self, terminal: &mut TerminalHandler, heading: &str, help: &str) {
+        terminal.clear();
+        self.win.clear();
+        self.win.draw_text(heading, Position::Coord(2, 1));
+        self.win.draw_text(help, Position::Coord(2, 2));
+        self.win.draw_element(&self.list);
+        self.win.render();
+        terminal.refresh().unwrap();
+    }
+
+    pub fn navigate(&mut self, k: KeyCode) {
+        match k {
+            KeyCode::Up => self.list.move_cursor(-1),
+            KeyCode::Down => self.list.move_cursor(1),
+            KeyCode::PageUp => self.list.move_cursor(-10),
+            KeyCode::PageDown => self.list.move_cursor(10),
+            _ => (),
+        }
+    }
+}
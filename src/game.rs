@@ -1,74 +1,351 @@
+pub mod animation;
 pub mod board;
 mod chat;
+pub mod clock;
+pub mod database;
+pub mod ggf;
+pub mod history;
+pub mod layout;
 pub mod macros;
+#[cfg(test)]
+mod mock_server;
+pub mod move_list;
 pub mod offline_game;
 mod online_game;
 pub mod online_lobby;
+pub mod position;
+pub mod puzzle;
+pub mod record;
+pub mod replay;
+pub mod search;
 pub mod socket;
+pub mod stats;
+pub mod wthor;
 
-use std::io::Write;
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
 
 use board::Board;
-use crossterm::{event::KeyCode, style::Color};
+use copypasta::{ClipboardContext, ClipboardProvider};
+use crossterm::{
+    event::{Event, KeyCode, MouseButton, MouseEvent, MouseEventKind},
+    style::Color,
+};
 
+use crate::settings::Settings;
 use crate::termin::{
     elements::{Rectangle, Text},
-    terminal_window::Terminal,
+    terminal_window::{Terminal, TerminalHandler},
     window::{Position, Window, WindowRef},
 };
+use crate::theme::Theme;
 
+use self::animation::FlipAnimation;
 use self::board::{Side, BLACK, DOWN, FIX, LEFT, RIGHT, UP, WHITE};
+use self::clock::Clock;
+use self::layout::Layout;
+use self::move_list::MoveList;
+use self::record::GameRecord;
+
+/// How often the clock is redrawn while waiting for a move.
+const CLOCK_TICK: Duration = Duration::from_millis(200);
+/// Where `board_container` sits in the game window, below the status lines.
+const BOARD_LEFT: u32 = 2;
+const BOARD_TOP: u32 = 4;
+/// Columns between the board and a side panel.
+const PANEL_GAP: u32 = 4;
+
+/// Shown in the status line until something else is, and again on `?`.
+pub const KEYS_HELP: &str = "d3: move  x: diagram  s: save  p: position  m: moves";
+
+fn copy_to_clipboard(text: String) -> bool {
+    match ClipboardContext::new() {
+        Ok(mut ctx) => ctx.set_contents(text).is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CursorResult {
+    Played,
+    Cancelled,
+    /// The clock of the side to move ran out.
+    Flagged,
+}
 
 #[derive(Debug)]
 pub struct Game {
     pub board: Board,
     pub cur_turn_side_win: WindowRef,
+    pub status_win: WindowRef,
+    pub record: GameRecord,
+    pub clock: Option<Clock>,
+    clock_win: WindowRef,
     cur_turn_side: Side,
     render_cursor: bool,
     render_available_moves: bool,
+    /// Column of a coordinate being typed, waiting for its row.
+    typed: Option<char>,
+    keys_help: &'static str,
+    animation: Option<FlipAnimation>,
+    /// Time between animation frames, `None` when animations are off.
+    frame_time: Option<Duration>,
+    /// Side panel listing the moves played, see `toggle_moves`.
+    moves: Option<MoveList>,
+    show_moves: bool,
     is_over: bool,
+    /// The window the game draws in, refitted by `resize`.
+    win: WindowRef,
+    panel_width: u32,
+    /// Windows kept at `panel_x` when the board changes size.
+    panels: Vec<WindowRef>,
 }
 
 impl Game {
+    /// A game with the move list next to the board.
     pub fn new(win: &mut WindowRef) -> Self {
-        let (width, height) = (32 - 2, 15);
-        let mut board_container = win.new_child(
-            Window::default()
-                .size(width + 4, height + 2)
-                .bg(Color::Green)
-                .xy(2, 4),
-        );
-        let board = board_container.new_child(Window::default().size(32 - 2, 15).xy(2, 1).bg(Color::Green));
-        let points_win = win.new_child(
-            Window::default()
-                .size(30, 1)
-                .xy(0, board_container.top() + board_container.height() + 2),
-        );
+        let mut game = Self::with_panel(win, move_list::WIDTH);
+        let height = game.board.board_container.height();
+        game.moves = Some(MoveList::new(win, game.panel_x(), BOARD_TOP, height));
+        game
+    }
+
+    /// Like `new`, leaving `panel_width` columns right of the board free
+    /// for a side panel at `panel_x`. The board takes the biggest layout
+    /// that fits in the rest of `win`.
+    pub fn with_panel(win: &mut WindowRef, panel_width: u32) -> Self {
+        let settings = Settings::load();
+        let theme = Theme::by_name(&settings.theme);
+        let mut board_container = win.new_child(Window::default().bg(theme.board));
+        let board = board_container.new_child(Window::default().bg(theme.board));
+        let points_win = win.new_child(Window::default());
         let cur_turn_side_win = win.new_child(Window::default().size(20, 1).xy(1, 1));
+        let status_win = win.new_child(Window::default().size(60, 1).xy(1, 2));
+        let clock_win = win.new_child(Window::default().size(40, 1).xy(1, 3));
 
-        Self {
+        let mut board = Board::new(board_container, board, points_win);
+        board.theme = theme;
+        board.free_cursor = settings.free_cursor;
+        board.show_labels = settings.coordinates;
+
+        let mut game = Self {
             is_over: false,
-            board: Board::new(board_container, board, points_win),
+            board,
             cur_turn_side_win,
+            status_win,
+            record: GameRecord::new(position::Position::initial()),
+            clock: None,
+            clock_win,
             cur_turn_side: BLACK,
             render_cursor: false,
             render_available_moves: false,
+            typed: None,
+            keys_help: KEYS_HELP,
+            animation: None,
+            frame_time: settings.animation.frame_time(),
+            moves: None,
+            show_moves: true,
+            win: win.clone(),
+            panel_width,
+            panels: vec![],
+        };
+        game.fit_board();
+        game.show_keys();
+        game
+    }
+
+    /// Gives the board the biggest layout that fits in `win` besides the
+    /// panel, moving everything placed around it along.
+    fn fit_board(&mut self) {
+        let layout = Layout::fit(
+            self.win
+                .width()
+                .saturating_sub(BOARD_LEFT + PANEL_GAP + self.panel_width),
+            self.win.height().saturating_sub(BOARD_TOP + 3),
+        );
+        let (width, height) = layout.size();
+        let (board_width, board_height) = layout.board_size();
+        let (offset_x, offset_y) = layout.offset();
+        let board = &mut self.board;
+        board.layout = layout;
+        board.board_container.set_size(width, height);
+        board
+            .board_container
+            .set_position(Position::Coord(BOARD_LEFT, BOARD_TOP));
+        board.board_win.set_size(board_width, board_height);
+        board
+            .board_win
+            .set_position(Position::Coord(offset_x, offset_y));
+        board.points_win.set_size(30, 1);
+        board
+            .points_win
+            .set_position(Position::Coord(0, BOARD_TOP + height + 2));
+
+        let panel_x = self.panel_x();
+        if let Some(moves) = &mut self.moves {
+            moves.place(panel_x, BOARD_TOP, height);
+        }
+        for panel in &mut self.panels {
+            panel.set_position(Position::Coord(panel_x, BOARD_TOP));
         }
     }
 
+    /// Keeps `panel` next to the board when it is refitted.
+    pub fn add_panel(&mut self, panel: &WindowRef) {
+        self.panels.push(panel.clone());
+    }
+
+    /// Refits the game to a terminal resized to `width` x `height` and
+    /// draws it again.
+    pub fn resize<W: Write>(&mut self, terminal: &mut Terminal<W>, width: u16, height: u16) {
+        terminal.root.set_size(width.into(), height.into());
+        self.win.set_size(width.into(), height.into());
+        self.fit_board();
+        self.redraw();
+    }
+
+    /// Draws every window of the game again, after the screen was cleared.
+    pub fn redraw(&mut self) {
+        self.cur_turn_side_win.render();
+        self.status_win.render();
+        self.clock_win.render();
+        for panel in &mut self.panels {
+            panel.render();
+        }
+        self.render_board();
+    }
+
+    /// Where the line under the board has room left, right of the points.
+    pub fn footer_xy(&self) -> (u32, u32) {
+        let points = &self.board.points_win;
+        (points.left() + points.width() + 2, points.top())
+    }
+
     pub fn init_board(&mut self) {
-        self.board.board[3][3] = BLACK;
-        self.board.board[3][4] = WHITE;
-        self.board.board[4][3] = WHITE;
-        self.board.board[4][4] = BLACK;
+        self.load_position(&position::Position::initial());
         self.board.move_cursor(3, 2);
-        self.board.black_points = 2;
-        self.board.white_points = 2;
+    }
+
+    pub fn load_position(&mut self, pos: &position::Position) {
+        self.show_position(pos);
+        self.record = GameRecord::new(*pos);
+        self.is_over = false;
+    }
+
+    /// Puts `pos` on the board without touching the record.
+    pub fn show_position(&mut self, pos: &position::Position) {
+        self.board.board = pos.board;
+        self.board.calc_points();
+        self.cur_turn_side = pos.side;
+        self.animation = None;
+        self.board.last_move = None;
+        self.board.last_flips.clear();
+    }
+
+    pub fn load_record(&mut self, record: GameRecord) {
+        self.load_position(&record.current());
+        self.record = record;
+    }
+
+    pub fn set_status(&mut self, msg: &str) {
+        self.status_win.clear();
+        self.status_win.draw_text(msg, Position::Coord(0, 0));
+        self.status_win.render();
+    }
+
+    pub fn show_keys(&mut self) {
+        self.set_status(self.keys_help);
+    }
+
+    /// Replaces `KEYS_HELP` for games with keys of their own.
+    pub fn set_keys_help(&mut self, help: &'static str) {
+        self.keys_help = help;
+        self.show_keys();
+    }
+
+    /// Left edge of the side panel `with_panel` left room for.
+    pub fn panel_x(&self) -> u32 {
+        let container = &self.board.board_container;
+        container.left() + container.width() + PANEL_GAP
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.board.theme
+    }
+
+    /// The position on the board, in the notation `position::Position::parse` reads.
+    pub fn position(&self) -> position::Position {
+        position::Position {
+            board: self.board.board,
+            side: self.cur_turn_side,
+        }
+    }
+
+    fn save_game(&mut self) {
+        let msg = match self.record.save() {
+            Ok(path) => {
+                let copied = copy_to_clipboard(self.record.to_transcript());
+                format!(
+                    "saved as {}{}",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    if copied { ", transcript copied" } else { "" }
+                )
+            }
+            Err(e) => format!("unable to save: {}", e),
+        };
+        self.set_status(&msg);
+    }
+
+    /// Saves the board as an SVG diagram and reports where it went.
+    pub fn save_diagram(&mut self, opts: &board::SvgOptions) {
+        let msg = match board::save_svg(&board::render_svg(&self.board.board, opts)) {
+            Ok(path) => format!(
+                "diagram saved as {}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            Err(e) => format!("unable to save diagram: {}", e),
+        };
+        self.set_status(&msg);
+    }
+
+    fn export_diagram(&mut self) {
+        let opts = board::SvgOptions {
+            coordinates: true,
+            legal_moves: self.position().legal_moves(),
+            last_move: self.record.moves_until(self.record.plies.len()).pop(),
+            numbers: vec![],
+        };
+        self.save_diagram(&opts);
+    }
+
+    fn copy_position(&mut self) {
+        let notation = self.position().to_string();
+        if copy_to_clipboard(notation.clone()) {
+            self.set_status(&format!("copied {}", notation));
+        } else {
+            self.set_status(&notation);
+        }
     }
 
     pub fn render_board(&mut self) {
         self.board.board_container.clear();
         self.board.render();
+        if let Some(animation) = &self.animation {
+            for ((row, col), shade) in animation.frame() {
+                let xy = self.board.layout.container_xy(row, col);
+                let side = self.board.board[row][col];
+                board::draw_square(
+                    &mut self.board.board_container,
+                    &self.board.theme,
+                    &self.board.layout,
+                    xy,
+                    side,
+                    shade,
+                );
+            }
+        }
         if self.render_available_moves {
             self.render_available_moves();
         }
@@ -78,16 +355,39 @@ impl Game {
 
         self.board.board_container.render();
         self.board.render_points();
+        self.render_moves();
+    }
+
+    fn render_moves(&mut self) {
+        if let (Some(moves), true) = (&mut self.moves, self.show_moves) {
+            moves.render(&self.record, self.record.plies.len());
+        }
+    }
+
+    /// Shows or hides the move list.
+    pub fn toggle_moves(&mut self) {
+        self.show_moves = !self.show_moves;
+        if let (Some(moves), false) = (&mut self.moves, self.show_moves) {
+            moves.hide();
+        }
+    }
+
+    fn scroll_moves(&mut self, dy: i16) {
+        if let (Some(moves), true) = (&mut self.moves, self.show_moves) {
+            moves.scroll(dy);
+        }
     }
 
     pub fn render_available_moves(&mut self) {
-        let mut b = Rectangle::default().bg(Color::Blue).size(2, 1);
+        let (width, height) = self.board.layout.cell;
+        let mut b = Rectangle::default()
+            .bg(self.board.theme.legal_move)
+            .size(width, height);
 
         for (row_idx, row) in &self.board.available_moves {
             for col_idx in row {
-                let x = col_idx * 4 + 2;
-                let y = row_idx * 2 + 1;
-                b.set_xy(x as u32, y as u32);
+                let (x, y) = self.board.layout.container_xy(*row_idx, *col_idx);
+                b.set_xy(x, y);
                 self.board.board_container.draw_element(&b);
             }
         }
@@ -95,6 +395,21 @@ impl Game {
         self.board.board_container.render();
     }
 
+    /// Draws `mark` over each square in `fg`, keeping the disc colour underneath.
+    pub fn mark_squares(&mut self, squares: &[position::Square], mark: &str, fg: Color) {
+        for (row, col) in squares {
+            let xy = self.board.layout.container_xy(*row, *col);
+            board::draw_mark(
+                &mut self.board.board_container,
+                &self.board.layout,
+                xy,
+                mark,
+                fg,
+            );
+        }
+        self.board.board_container.render();
+    }
+
     pub fn render_cur_turn_side(&mut self) {
         self.cur_turn_side_win.clear();
         let text_box = Text::default().text(if self.cur_turn_side == WHITE {
@@ -106,6 +421,53 @@ impl Game {
         self.cur_turn_side_win.render();
     }
 
+    pub fn render_clock(&mut self) {
+        let clock = match &self.clock {
+            Some(clock) => clock,
+            None => return,
+        };
+        let now = Instant::now();
+        let text = format!(
+            "black {}   white {}",
+            clock.display(BLACK, now),
+            clock.display(WHITE, now)
+        );
+        self.clock_win.clear();
+        self.clock_win.draw_text(&text, Position::Coord(0, 0));
+        self.clock_win.render();
+    }
+
+    /// Starts the clock of the side to move, stopping the other one.
+    pub fn start_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            clock.start(self.cur_turn_side, Instant::now());
+        }
+    }
+
+    pub fn stop_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            clock.stop(Instant::now());
+        }
+    }
+
+    pub fn pause_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            clock.pause(Instant::now());
+        }
+    }
+
+    pub fn resume_clock(&mut self) {
+        if let Some(clock) = &mut self.clock {
+            clock.resume(Instant::now());
+        }
+    }
+
+    pub fn flagged(&self) -> Option<Side> {
+        self.clock
+            .as_ref()
+            .and_then(|clock| clock.flagged(Instant::now()))
+    }
+
     pub fn set_cur_turn_side(&mut self, side: Side) {
         self.cur_turn_side = side;
     }
@@ -119,7 +481,53 @@ impl Game {
     }
 
     pub fn play_move(&mut self) {
+        let (col, row) = self.board.cursor_xy();
+        let square = (row as usize, col as usize);
+        let flips = self.position().flips(square.0, square.1);
+        self.record.play(self.cur_turn_side, square);
         self.board.play_move(self.cur_turn_side);
+        self.board.last_move = Some(square);
+        self.board.last_flips = flips.clone();
+        self.animation = match self.frame_time {
+            Some(frame_time) if !flips.is_empty() => Some(FlipAnimation::new(
+                square,
+                &flips,
+                self.cur_turn_side,
+                &self.board.theme,
+                frame_time,
+            )),
+            _ => None,
+        };
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    /// Draws the board with the current animation frame and moves on.
+    /// Returns how long to wait for the next frame, `None` once the
+    /// animation is over and the final position is drawn.
+    pub fn animation_frame(&mut self) -> Option<Duration> {
+        if self.animation.as_ref().is_some_and(|a| a.is_done()) {
+            self.animation = None;
+        }
+        self.render_board();
+        let animation = self.animation.as_mut()?;
+        animation.advance();
+        Some(animation.frame_time())
+    }
+
+    /// Runs the animation of the last move to the end, blocking meanwhile.
+    pub fn play_animation<W: Write>(&mut self, terminal: &mut Terminal<W>) {
+        while let Some(delay) = self.animation_frame() {
+            terminal.refresh().unwrap();
+            std::thread::sleep(delay);
+        }
+        terminal.refresh().unwrap();
+    }
+
+    pub fn stop_animation(&mut self) {
+        self.animation = None;
     }
 
     pub fn is_game_draw(&self) -> bool {
@@ -132,7 +540,12 @@ impl Game {
 
     pub fn render_game_over(&mut self, win: &mut WindowRef, msg: &str) {
         self.board.calc_points();
-        let mut border = win.new_child(Window::default().bg(Color::Green).size(24, 8).xy(6, 7));
+        let container = &self.board.board_container;
+        let mut border =
+            win.new_child(Window::default().bg(self.board.theme.board).size(24, 8).xy(
+                container.left() + container.width().saturating_sub(24) / 2,
+                container.top() + container.height().saturating_sub(8) / 2,
+            ));
         let mut game_over_win = border.new_child(Window::default().size(20, 6));
         let mut text_box = Text::default()
             .text("Game Over")
@@ -164,36 +577,172 @@ impl Game {
         border.delete();
     }
 
-    pub fn keyboard_event(&mut self, k: KeyCode) -> bool {
+    /// Waits for a key after the game is over, opening the replay on `r`.
+    pub fn offer_replay(&mut self, terminal: &mut TerminalHandler) {
+        self.set_status("r: replay game  any other key: continue");
+        terminal.refresh().unwrap();
+        if terminal.getch() == KeyCode::Char('r') {
+            replay::replay(terminal, self.record.clone());
+        }
+    }
+
+    /// Handles a key of a typed coordinate such as `d3`. Returns `None` when
+    /// `k` is not part of one, and `Some(true)` once the cursor is on the
+    /// legal move that was typed.
+    pub fn coordinate_key(&mut self, k: KeyCode) -> Option<bool> {
+        if !self.render_cursor {
+            return None;
+        }
+        let k = match k {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            k => k,
+        };
+        match (self.typed, k) {
+            (_, KeyCode::Char(col @ 'a'..='h')) => {
+                self.typed = Some(col);
+                self.set_status(&format!("move: {}_", col));
+            }
+            (Some(col), KeyCode::Char(row @ '1'..='8')) => {
+                self.typed = None;
+                let name = format!("{}{}", col, row);
+                let (row, col) = position::parse_square(&name)?;
+                match self.position().why_illegal(row, col) {
+                    None => {
+                        self.show_keys();
+                        self.board.move_cursor(col as u16, row as u16);
+                        return Some(true);
+                    }
+                    Some(reason) => self.set_status(&reason),
+                }
+            }
+            (Some(_), KeyCode::Esc | KeyCode::Backspace) => {
+                self.typed = None;
+                self.show_keys();
+            }
+            (Some(col), KeyCode::Char(c)) => {
+                self.typed = None;
+                self.set_status(&format!("{}{} is not a square, type a-h then 1-8", col, c));
+            }
+            (None, KeyCode::Char(c)) if c.is_ascii_digit() => {
+                self.set_status("type the column (a-h) before the row");
+            }
+            _ => return None,
+        }
+        Some(false)
+    }
+
+    pub fn keyboard_event(&mut self, k: KeyCode) -> Option<CursorResult> {
+        match self.coordinate_key(k) {
+            Some(true) => {
+                self.play_move();
+                return Some(CursorResult::Played);
+            }
+            Some(false) => return None,
+            None => (),
+        }
         match k {
             KeyCode::Up => self.board.move_cursor_rel(FIX, UP),
             KeyCode::Down => self.board.move_cursor_rel(FIX, DOWN),
             KeyCode::Left => self.board.move_cursor_rel(LEFT, FIX),
             KeyCode::Right => self.board.move_cursor_rel(RIGHT, FIX),
-            KeyCode::Enter => {
+            KeyCode::Enter if self.cursor_move_is_legal() => {
                 self.play_move();
-                return true;
+                return Some(CursorResult::Played);
             }
-            KeyCode::Esc => return true,
+            KeyCode::Esc => return Some(CursorResult::Cancelled),
+            KeyCode::Char('s') => self.save_game(),
+            KeyCode::Char('p') => self.copy_position(),
+            KeyCode::Char('x') => self.export_diagram(),
+            KeyCode::Char('m') => self.toggle_moves(),
+            KeyCode::Char('?') => self.show_keys(),
+            KeyCode::PageUp => self.scroll_moves(-1),
+            KeyCode::PageDown => self.scroll_moves(1),
             _ => (),
         }
-        return false;
+        None
     }
 
-    pub fn enable_cursor_movement<W: Write>(&mut self, terminal: &mut Terminal<W>) {
+    /// Checks the square under the cursor, explaining in the status line why
+    /// it can't be played.
+    pub fn cursor_move_is_legal(&mut self) -> bool {
+        let (col, row) = self.board.cursor_xy();
+        match self.position().why_illegal(row as usize, col as usize) {
+            Some(reason) => {
+                self.set_status(&reason);
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Moves the cursor to the square under the mouse. Returns true when
+    /// that square was clicked.
+    pub fn point_cursor(&mut self, m: MouseEvent) -> bool {
+        let clicked = match m.kind {
+            MouseEventKind::Moved | MouseEventKind::Drag(_) => false,
+            MouseEventKind::Down(MouseButton::Left) => true,
+            _ => return false,
+        };
+        match self.board.cursor_square_at(m.column, m.row) {
+            Some((row, col)) => {
+                self.board.move_cursor(col as u16, row as u16);
+                clicked
+            }
+            None => false,
+        }
+    }
+
+    pub fn mouse_event(&mut self, m: MouseEvent) -> Option<CursorResult> {
+        match m.kind {
+            MouseEventKind::ScrollUp => self.scroll_moves(-1),
+            MouseEventKind::ScrollDown => self.scroll_moves(1),
+            _ => (),
+        }
+        if self.point_cursor(m) && self.cursor_move_is_legal() {
+            self.play_move();
+            return Some(CursorResult::Played);
+        }
+        None
+    }
+
+    pub fn enable_cursor_movement<W: Write>(&mut self, terminal: &mut Terminal<W>) -> CursorResult {
         self.render_cursor = true;
         self.render_available_moves = true;
+        self.typed = None;
         self.render_board();
         terminal.refresh().unwrap();
-        loop {
-            if self.keyboard_event(terminal.getch()) {
-                break;
+        let result = loop {
+            let event = match self.clock {
+                Some(_) => terminal.poll_event(CLOCK_TICK),
+                None => Some(terminal.event()),
+            };
+            if self.flagged() == Some(self.cur_turn_side) {
+                break CursorResult::Flagged;
+            }
+            let result = match event {
+                Some(Event::Key(k)) => self.keyboard_event(k.code),
+                Some(Event::Mouse(m)) => self.mouse_event(m),
+                Some(Event::Resize(width, height)) => {
+                    self.resize(terminal, width, height);
+                    None
+                }
+                Some(_) => None,
+                None => {
+                    self.render_clock();
+                    terminal.draw_window(&self.clock_win).unwrap();
+                    terminal.flush().unwrap();
+                    continue;
+                }
+            };
+            if let Some(result) = result {
+                break result;
             }
             self.render_board();
             terminal.refresh().unwrap();
-        }
+        };
         self.render_cursor = false;
         self.render_available_moves = false;
+        result
     }
 
     pub fn check_is_over(&mut self) {
@@ -202,3 +751,64 @@ impl Game {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage;
+    use crate::termin::crossterm_handler::ScriptedInput;
+
+    #[test]
+    fn typed_coordinates_play_only_legal_moves() {
+        let _data = storage::temp_data_dir();
+        let mut win = WindowRef::new(80, 24, 0, 0);
+        let mut game = Game::new(&mut win);
+        game.init_board();
+        game.board.calc_available_moves(BLACK);
+        game.render_cursor = true;
+
+        assert_eq!(game.keyboard_event(KeyCode::Char('3')), None);
+        assert_eq!(game.keyboard_event(KeyCode::Char('a')), None);
+        assert_eq!(game.keyboard_event(KeyCode::Char('1')), None);
+        assert_eq!(game.keyboard_event(KeyCode::Char('D')), None);
+        assert_eq!(game.typed, Some('d'));
+        assert_eq!(
+            game.keyboard_event(KeyCode::Char('3')),
+            Some(CursorResult::Played)
+        );
+        assert_eq!(game.record.to_transcript(), "d3");
+    }
+
+    #[test]
+    fn board_remembers_the_last_move_until_a_new_position() {
+        let _data = storage::temp_data_dir();
+        let mut win = WindowRef::new(80, 24, 0, 0);
+        let mut game = Game::new(&mut win);
+        game.init_board();
+        game.board.move_cursor(3, 2);
+        game.play_move();
+        assert_eq!(game.board.last_move, Some((2, 3)));
+        assert_eq!(game.board.last_flips, vec![(3, 3)]);
+
+        game.load_position(&position::Position::initial());
+        assert_eq!(game.board.last_move, None);
+        assert!(game.board.last_flips.is_empty());
+    }
+
+    #[test]
+    fn refits_the_board_when_the_terminal_is_resized() {
+        let _data = storage::temp_data_dir();
+        let mut terminal = mock_server::terminal(&ScriptedInput::default());
+        let mut win = terminal.root.new_child(Window::default().size(120, 40));
+        let mut game = Game::new(&mut win);
+        assert!(game.board.layout.borders);
+        let container = game.board.board_container.clone();
+        assert!(game.footer_xy().1 > container.top() + container.height());
+
+        game.resize(&mut terminal, 80, 24);
+        assert_eq!(game.board.layout, Layout::normal());
+        assert_eq!((win.width(), terminal.root.height()), (80, 24));
+        assert_eq!(game.footer_xy().1, 23);
+        assert_eq!(game.panel_x(), BOARD_LEFT + 34 + PANEL_GAP);
+    }
+}
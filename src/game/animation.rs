@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use crossterm::style::Color;
+
+use super::{
+    board::{Side, WHITE},
+    position::{opponent_of, Square},
+};
+use crate::theme::Theme;
+
+/// Flipped discs turning over after a move, ring by ring outward from the
+/// placed disc. The board already holds the final position; a frame only
+/// says which discs are still showing an older shade.
+#[derive(Debug)]
+pub struct FlipAnimation {
+    /// Each flipped square with its distance from the placed disc.
+    flips: Vec<(Square, usize)>,
+    /// From the old colour to the new one, without either end.
+    shades: [Color; 2],
+    from: Color,
+    frame: usize,
+    frame_time: Duration,
+}
+
+impl FlipAnimation {
+    pub fn new(
+        placed: Square,
+        flips: &[Square],
+        flipped_to: Side,
+        theme: &Theme,
+        frame_time: Duration,
+    ) -> Self {
+        let ring = |(row, col): Square| row.abs_diff(placed.0).max(col.abs_diff(placed.1));
+        let [dark, light] = theme.flip_shades;
+        let shades = if flipped_to == WHITE {
+            [dark, light]
+        } else {
+            [light, dark]
+        };
+        Self {
+            flips: flips.iter().map(|sq| (*sq, ring(*sq))).collect(),
+            shades,
+            from: theme.disc(opponent_of(flipped_to)),
+            frame: 0,
+            frame_time,
+        }
+    }
+
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+
+    /// Colour of every disc that hasn't finished turning in this frame.
+    pub fn frame(&self) -> Vec<(Square, Color)> {
+        self.flips
+            .iter()
+            .filter_map(|(sq, ring)| {
+                // ring 1 shows its first shade on frame 0
+                match (self.frame + 1).checked_sub(ring - 1) {
+                    None | Some(0) => Some((*sq, self.from)),
+                    Some(step) => self.shades.get(step - 1).map(|shade| (*sq, *shade)),
+                }
+            })
+            .collect()
+    }
+
+    pub fn advance(&mut self) {
+        self.frame += 1;
+    }
+
+    /// True once every disc shows its new colour.
+    pub fn is_done(&self) -> bool {
+        self.frame().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::BLACK;
+
+    #[test]
+    fn rings_turn_outward_one_frame_apart() {
+        let theme = Theme::classic();
+        let mut anim = FlipAnimation::new((0, 0), &[(0, 1), (0, 2)], WHITE, &theme, Duration::ZERO);
+        assert_eq!(
+            anim.frame(),
+            vec![((0, 1), Color::DarkGrey), ((0, 2), theme.disc(BLACK))]
+        );
+        anim.advance();
+        assert_eq!(
+            anim.frame(),
+            vec![((0, 1), Color::Grey), ((0, 2), Color::DarkGrey)]
+        );
+        anim.advance();
+        assert_eq!(anim.frame(), vec![((0, 2), Color::Grey)]);
+        assert!(!anim.is_done());
+        anim.advance();
+        assert!(anim.is_done());
+    }
+}
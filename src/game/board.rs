@@ -1,15 +1,16 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fs, path::PathBuf};
 
 use crossterm::style::Color;
 
+use super::layout::Layout;
+use super::position::Square;
+pub use super::position::{Side, BLACK, EMPTY, WHITE};
+use crate::storage;
 use crate::termin::{
     elements::{Rectangle, Text},
     window::WindowRef,
 };
-
-pub const BLACK: Side = 'b';
-pub const WHITE: Side = 'w';
-pub const EMPTY: Side = '0';
+use crate::theme::{glyph, Theme};
 
 pub const LEFT: i8 = -1;
 pub const RIGHT: i8 = 1;
@@ -17,7 +18,192 @@ pub const UP: i8 = -1;
 pub const DOWN: i8 = 1;
 pub const FIX: i8 = 0;
 
-pub type Side = char;
+/// Draws a square of `layout` at `(x, y)` of `win` holding `side` in
+/// `color`: a solid block, or a glyph on the empty colour when the theme
+/// asks for one.
+pub fn draw_square(
+    win: &mut WindowRef,
+    theme: &Theme,
+    layout: &Layout,
+    (x, y): (u32, u32),
+    side: Side,
+    color: Color,
+) {
+    let (width, height) = layout.cell;
+    let mut square = Rectangle::default().size(width, height).xy(x, y);
+    if side == EMPTY || !theme.glyphs {
+        win.draw_element(&square.bg(color));
+        return;
+    }
+    square.set_bg(theme.empty);
+    win.draw_element(&square);
+    let (dx, dy) = layout.center();
+    win.draw_element(
+        &Text::default()
+            .text(glyph(side))
+            .size(1, 1)
+            .xy(x + dx, y + dy)
+            .fg(color),
+    );
+}
+
+/// Draws the two characters of `mark` on the left and right edge of square
+/// `(x, y)` of `win`, keeping what is underneath. Squares one cell wide only
+/// get the first.
+pub fn draw_mark(win: &mut WindowRef, layout: &Layout, (x, y): (u32, u32), mark: &str, fg: Color) {
+    let (width, _) = layout.cell;
+    let y = y + layout.center().1;
+    let mut chars = mark.chars();
+    let mut text = Text::default().size(1, 1).fg(fg);
+    if let Some(ch) = chars.next() {
+        text.set_text(&ch.to_string());
+        text.set_xy(x, y);
+        win.draw_element(&text);
+    }
+    if let (Some(ch), true) = (chars.next(), width > 1) {
+        text.set_text(&ch.to_string());
+        text.set_xy(x + width - 1, y);
+        win.draw_element(&text);
+    }
+}
+
+/// What to draw besides the discs in `render_svg`.
+#[derive(Debug, Default, Clone)]
+pub struct SvgOptions {
+    pub coordinates: bool,
+    pub legal_moves: Vec<Square>,
+    pub last_move: Option<Square>,
+    /// Move numbers to print on discs.
+    pub numbers: Vec<(Square, usize)>,
+}
+
+/// Pixels per terminal column and row. Terminal cells are about twice as
+/// tall as they are wide, which keeps the squares square.
+const SVG_COL: u32 = 10;
+const SVG_ROW: u32 = 20;
+const SVG_MARGIN: u32 = 20;
+
+fn hex(color: Color) -> String {
+    match color {
+        Color::Rgb { r, g, b } => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Black => "#000000".to_string(),
+        Color::White => "#ffffff".to_string(),
+        Color::Green => "#00ff00".to_string(),
+        Color::Blue => "#0000ff".to_string(),
+        Color::Yellow => "#ffff00".to_string(),
+        Color::Red => "#ff0000".to_string(),
+        _ => "#808080".to_string(),
+    }
+}
+
+/// Draws `board` as an SVG diagram, laid out like `Board::render` in the
+/// normal layout with each terminal cell scaled to `SVG_COL` x `SVG_ROW` pixels.
+/// Diagrams are shared and printed away from the terminal, so they keep the
+/// classic colours whatever theme is picked.
+pub fn render_svg(board: &[[Side; 8]; 8], opts: &SvgOptions) -> String {
+    let theme = Theme::classic();
+    let layout = Layout::normal();
+    let size = layout.size();
+    let margin = if opts.coordinates { SVG_MARGIN } else { 0 };
+    let (width, height) = (size.0 * SVG_COL + margin, size.1 * SVG_ROW + margin);
+    let center = |row: usize, col: usize| {
+        let (x, y) = layout.container_xy(row, col);
+        (
+            margin + x * SVG_COL + SVG_COL,
+            margin + y * SVG_ROW + SVG_ROW / 2,
+        )
+    };
+    let radius = SVG_COL - 1;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = width,
+        h = height
+    );
+    svg += &format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+        margin,
+        margin,
+        width - margin,
+        height - margin,
+        hex(theme.board)
+    );
+
+    if opts.coordinates {
+        for idx in 0..8 {
+            let (x, _) = center(0, idx);
+            let (_, y) = center(idx, 0);
+            svg += &format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"14\" text-anchor=\"middle\">{}</text>\n",
+                x,
+                SVG_MARGIN - 6,
+                (b'a' + idx as u8) as char
+            );
+            svg += &format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"14\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                SVG_MARGIN / 2,
+                y,
+                idx + 1
+            );
+        }
+    }
+
+    for (row_idx, row) in board.iter().enumerate() {
+        for (col_idx, side) in row.iter().enumerate() {
+            let (x, y) = center(row_idx, col_idx);
+            let fill = if *side == EMPTY && opts.legal_moves.contains(&(row_idx, col_idx)) {
+                theme.legal_move
+            } else {
+                theme.disc(*side)
+            };
+            svg += &format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+                x,
+                y,
+                radius,
+                hex(fill)
+            );
+        }
+    }
+
+    if let Some((row, col)) = opts.last_move {
+        let (x, y) = center(row, col);
+        svg += &format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>\n",
+            x,
+            y,
+            radius + 3,
+            hex(theme.mark)
+        );
+    }
+
+    for ((row, col), number) in &opts.numbers {
+        let (x, y) = center(*row, *col);
+        let fg = if board[*row][*col] == BLACK {
+            Color::White
+        } else {
+            Color::Black
+        };
+        svg += &format!(
+            "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"10\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>\n",
+            x,
+            y,
+            hex(fg),
+            number
+        );
+    }
+
+    svg + "</svg>\n"
+}
+
+/// Writes `svg` to `<data>/diagrams`.
+pub fn save_svg(svg: &str) -> Result<PathBuf, String> {
+    let dir = storage::data_path("diagrams")?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("position-{}.svg", storage::timestamp()));
+    fs::write(&path, svg).map_err(|e| e.to_string())?;
+    Ok(path)
+}
 
 #[derive(Debug)]
 struct Cursor {
@@ -36,6 +222,15 @@ pub struct Board {
     pub white_points: u8,
     cursor: Cursor,
     pub available_moves: HashMap<usize, Vec<usize>>,
+    /// The cursor may rest on any square, see `Settings::free_cursor`.
+    pub free_cursor: bool,
+    /// Draws the a-h and 1-8 labels around the squares.
+    pub show_labels: bool,
+    pub theme: Theme,
+    pub layout: Layout,
+    /// The square played last and the discs it flipped, marked until the next move.
+    pub last_move: Option<Square>,
+    pub last_flips: Vec<Square>,
 }
 
 const TRAV_ARR: [(i8, i8); 8] = [
@@ -59,11 +254,17 @@ impl Board {
             cursor: Cursor {
                 x: 0,
                 y: 0,
-                el: Rectangle::default().bg(Color::Yellow).size(2, 1),
+                el: Rectangle::default().size(2, 1),
             },
             white_points: 0,
             black_points: 0,
             available_moves: HashMap::new(),
+            free_cursor: false,
+            show_labels: true,
+            theme: Theme::classic(),
+            layout: Layout::normal(),
+            last_move: None,
+            last_flips: vec![],
         }
     }
 
@@ -72,37 +273,47 @@ impl Board {
     }
 
     pub fn render(&mut self) {
-        let mut pos_x = 0;
-        let mut pos_y = 0;
-        let mut cell = Rectangle::default()
-            .size(2, 1)
-            .xy(pos_x, pos_y)
-            .bg(Color::Green);
-
-        for row in self.board {
-            pos_x = 0;
-            for col in row {
-                cell.set_xy(pos_x, pos_y);
-                cell.set_bg(if col == WHITE {
-                    Color::White
-                } else if col == BLACK {
-                    Color::Black
+        for (row_idx, row) in self.board.iter().enumerate() {
+            for (col_idx, side) in row.iter().enumerate() {
+                let color = if self.last_flips.contains(&(row_idx, col_idx)) {
+                    self.theme.tint(*side)
                 } else {
-                    Color::Rgb {
-                        r: 80,
-                        g: 220,
-                        b: 120,
-                    }
-                });
-
-                self.board_win.draw_element(&cell);
-
-                pos_x += 4;
+                    self.theme.disc(*side)
+                };
+                let xy = self.layout.cell_xy(row_idx, col_idx);
+                draw_square(
+                    &mut self.board_win,
+                    &self.theme,
+                    &self.layout,
+                    xy,
+                    *side,
+                    color,
+                );
             }
-            pos_y += 2;
+        }
+        if let Some((row, col)) = self.last_move {
+            let xy = self.layout.cell_xy(row, col);
+            draw_mark(&mut self.board_win, &self.layout, xy, "[]", self.theme.mark);
         }
 
         self.board_win.render_to_parent();
+
+        let mut line = Text::default().fg(self.theme.label);
+        for (x, y, text) in self.layout.grid_lines() {
+            line.set_text(&text);
+            line.set_size(text.chars().count() as u32, 1);
+            line.set_xy(x, y);
+            self.board_container.draw_element(&line);
+        }
+
+        if self.show_labels {
+            let mut label = Text::default().size(1, 1).fg(self.theme.label);
+            for (x, y, ch) in self.layout.label_positions() {
+                label.set_xy(x, y);
+                label.set_text(&ch.to_string());
+                self.board_container.draw_element(&label);
+            }
+        }
     }
 
     pub fn render_points(&mut self) {
@@ -119,17 +330,42 @@ impl Board {
         self.points_win.render();
     }
 
+    /// Puts the cursor on a legal move. A free cursor is left where it is.
     pub fn place_cursor_on_legal_position(&mut self) {
+        if self.free_cursor {
+            return;
+        }
         let row_idx = self.available_moves.keys().next().unwrap_or(&0);
         let col_idx = self.available_moves.get(row_idx).unwrap_or(&vec![0])[0];
 
         self.move_cursor(col_idx as u16, *row_idx as u16);
     }
 
+    pub fn is_legal(&self, row: usize, col: usize) -> bool {
+        self.available_moves
+            .get(&row)
+            .is_some_and(|cols| cols.contains(&col))
+    }
+
+    /// Square under the screen cell `(column, row)` the cursor may move to:
+    /// any square for a free cursor, otherwise only legal moves.
+    pub fn cursor_square_at(&self, column: u16, row: u16) -> Option<Square> {
+        let (x, y) = self.board_container.hit(column, row)?;
+        let (row, col) = self.layout.square_at(x, y)?;
+        (self.free_cursor || self.is_legal(row, col)).then_some((row, col))
+    }
+
     pub fn render_cursor(&mut self) {
-        let x = self.cursor.x * 4 + 2;
-        let y = self.cursor.y * 2 + 1;
-        self.cursor.el.set_xy(x.into(), y.into());
+        let (row, col) = (self.cursor.y as usize, self.cursor.x as usize);
+        let (x, y) = self.layout.container_xy(row, col);
+        let (width, height) = self.layout.cell;
+        self.cursor.el.set_xy(x, y);
+        self.cursor.el.set_size(width, height);
+        self.cursor.el.set_bg(if self.is_legal(row, col) {
+            self.theme.cursor
+        } else {
+            self.theme.illegal_cursor
+        });
         self.board_container.draw_element(&self.cursor.el);
     }
 
@@ -139,12 +375,21 @@ impl Board {
     }
 
     pub fn move_cursor_rel(&mut self, dx: i8, dy: i8) {
+        if self.free_cursor {
+            self.cursor.x = (self.cursor.x as i8 + dx).clamp(0, 7) as u16;
+            self.cursor.y = (self.cursor.y as i8 + dy).clamp(0, 7) as u16;
+            return;
+        }
+
         let mut row_idx = self.cursor.y as i8;
-        let row = self.available_moves.get(&(row_idx as usize)).unwrap();
-        let mut col_idx = row
-            .iter()
-            .position(|x| *x == (self.cursor.x as usize))
-            .unwrap() as i8;
+        let row = match self.available_moves.get(&(row_idx as usize)) {
+            Some(row) => row,
+            None => return self.place_cursor_on_legal_position(),
+        };
+        let mut col_idx = match row.iter().position(|x| *x == (self.cursor.x as usize)) {
+            Some(idx) => idx as i8,
+            None => return self.place_cursor_on_legal_position(),
+        };
 
         if dy == FIX {
             col_idx += dx;
@@ -390,3 +635,29 @@ impl Board {
         return p;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::position::Position;
+
+    #[test]
+    fn svg_has_a_disc_per_square_and_the_markers() {
+        let pos = Position::initial();
+        let svg = render_svg(
+            &pos.board,
+            &SvgOptions {
+                coordinates: true,
+                legal_moves: pos.legal_moves(),
+                last_move: Some((3, 3)),
+                numbers: vec![((4, 4), 1)],
+            },
+        );
+
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<circle").count(), 65);
+        assert_eq!(svg.matches("fill=\"#0000ff\"").count(), 4);
+        assert!(svg.contains(">h</text>") && svg.contains(">8</text>"));
+        assert!(svg.contains(">1</text>"));
+    }
+}
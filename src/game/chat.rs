@@ -1,7 +1,10 @@
+use std::io::Write;
+
 use crossterm::{event::Event, style::Color, queue, execute, cursor::MoveTo};
 
 use crate::{
     sleep,
+    theme::Theme,
     termin::{
         elements::{InputWindow, Text, Rectangle},
         terminal_window::TerminalHandler,
@@ -31,6 +34,7 @@ pub struct ChatSection {
     is_online: bool,
     receiver_name: String,
     next_y_pos: u32,
+    theme: Theme,
 }
 
 enum MsgDir<'a> {
@@ -39,21 +43,23 @@ enum MsgDir<'a> {
 }
 
 impl<'a> MsgDir<'a> {
-    fn to_chat_msg(&self, name: &'a str) -> ChatMsg {
-        return match &self {
-            MsgDir::Send(msg) => ChatMsg::new(name, msg, Color::Blue),
-            MsgDir::Receive(msg) => ChatMsg::new(name, msg, Color::Red)
+    fn to_chat_msg(&self, name: &'a str, theme: &Theme) -> ChatMsg<'a> {
+        return match *self {
+            MsgDir::Send(msg) => ChatMsg::new(name, msg, theme.my_chat),
+            MsgDir::Receive(msg) => ChatMsg::new(name, msg, theme.their_chat)
         }
     }
 }
 
 impl ChatSection {
-    pub fn new(win: &mut WindowRef, receiver_name: String) -> Self {
+    /// A chat with `receiver_name`, echoing the last message at `recent_xy`
+    /// of `win` while the board is shown.
+    pub fn new(win: &mut WindowRef, receiver_name: String, (x, y): (u32, u32)) -> Self {
         let height = 22;
         let width = 50;
         let mut chat_section = win.new_child(Window::default().size(width, height).xy(0, 2));
         let chat_msgs = chat_section.new_child(Window::default().size(width, height - 4).xy(0, 2).scoll_size(width, 100));
-        let recent_chat = win.new_child(Window::default().size(width, 2).xy(1, 25));
+        let recent_chat = win.new_child(Window::default().size(width, 2).xy(x, y));
         let input_win = InputWindow::from(
             &mut chat_section,
             Window::default().size(width - 2, 1).xy(2, height - 1)
@@ -77,7 +83,8 @@ impl ChatSection {
             input_win,
             next_y_pos: 0,
             receiver_name,
-            is_online: false
+            is_online: false,
+            theme: Theme::load(),
         }
     }
 
@@ -87,7 +94,7 @@ impl ChatSection {
 
     pub fn set_recvr_is_online(&mut self, is_online: bool) {
         self.is_online = is_online;
-        self.draw_conn_status(if is_online { Color::Green } else { Color::Red });
+        self.draw_conn_status(if is_online { self.theme.online } else { self.theme.offline });
     }
 
     pub fn render(&mut self) {
@@ -97,7 +104,7 @@ impl ChatSection {
     }
 
     fn push_chat_msg(&mut self, msg_dir: MsgDir) {
-        let msg = msg_dir.to_chat_msg(&self.receiver_name);
+        let msg = msg_dir.to_chat_msg(&self.receiver_name, &self.theme);
         let msg_text = ": ".to_string() + msg.msg;
         let msg_height = ((msg.name.len() + msg_text.len()) / self.chat_msgs.width() as usize + 1) as u32;
 
@@ -144,21 +151,21 @@ impl ChatSection {
         self.chat_msgs.set_scroll_xy_rel(0, 1);
     }
 
-    pub fn clear_input_win(&mut self) {
+    pub fn clear_input_win<W: Write>(&mut self, out: &mut W) {
         self.input_win.clear();
         self.input_win.update_rel_xy();
-        self.input_win.update_cursor();
+        self.input_win.update_cursor(out);
     }
 
-    pub fn enable_cursor(&mut self) {
+    pub fn enable_cursor<W: Write>(&mut self, out: &mut W) {
         self.input_win.set_abs_xy();
-        self.input_win.show_cursor();
+        self.input_win.show_cursor(out);
         self.input_win.update_rel_xy();
-        self.input_win.update_cursor();
+        self.input_win.update_cursor(out);
     }
 
-    pub fn disable_cursor(&mut self) {
-        self.input_win.hide_cursor();
+    pub fn disable_cursor<W: Write>(&mut self, out: &mut W) {
+        self.input_win.hide_cursor(out);
     }
 
     pub fn handle_kbd(&mut self, e: Event) {
@@ -168,7 +175,7 @@ impl ChatSection {
 
 #[cfg(test)]
 mod tests {
-    use crate::termin::{self, crossterm_handler::CrosstermHandler};
+    use crate::termin::{self, crossterm_handler::CrosstermHandler, recorder::Recorder};
     use crossterm::{
         cursor,
         event::KeyCode,
@@ -183,18 +190,18 @@ mod tests {
     fn chat_section() {
         enable_raw_mode().unwrap();
         execute!(stdout(), EnterAlternateScreen, cursor::Hide).unwrap();
-        let mut terminal = termin::root(CrosstermHandler::new(stdout()));
+        let mut terminal = termin::root(CrosstermHandler::new(Recorder::new(stdout())));
 
-        let mut chat_sec = ChatSection::new(&mut terminal.root, String::from("test jinga boy"));
+        let mut chat_sec = ChatSection::new(&mut terminal.root, String::from("test jinga boy"), (1, 25));
         chat_sec.render();
         terminal.refresh().unwrap();
 
-        chat_sec.enable_cursor();
+        chat_sec.enable_cursor(&mut terminal.handler);
         terminal.flush().unwrap();
 
         loop {
             let e = terminal.event();
-            chat_sec.input_win.hide_cursor();
+            chat_sec.input_win.hide_cursor(&mut terminal.handler);
             match e {
                 Event::Key(k) => match k.code {
                     KeyCode::Enter => {
@@ -221,8 +228,8 @@ mod tests {
                 },
                 _ => continue,
             }
-            chat_sec.input_win.show_cursor();
-            chat_sec.input_win.update_cursor();
+            chat_sec.input_win.show_cursor(&mut terminal.handler);
+            chat_sec.input_win.update_cursor(&mut terminal.handler);
             terminal.flush().unwrap();
         }
 
@@ -0,0 +1,290 @@
+use std::time::{Duration, Instant};
+
+use super::board::{Side, BLACK, WHITE};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overtime {
+    None,
+    /// Added to the clock after every move.
+    Increment(Duration),
+    /// Once the main time is gone, every move must be made within `period`;
+    /// each overrun costs one of the `periods`.
+    ByoYomi {
+        period: Duration,
+        periods: u32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub overtime: Overtime,
+}
+
+fn parse_number(s: &str) -> Result<f64, String> {
+    s.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| *n >= 0.0)
+        .ok_or(format!("invalid number '{}'", s.trim()))
+}
+
+/// `s` units of `unit` seconds each.
+fn parse_duration(s: &str, unit: f64) -> Result<Duration, String> {
+    Duration::try_from_secs_f64(parse_number(s)? * unit)
+        .map_err(|e| format!("invalid time '{}': {}", s.trim(), e))
+}
+
+impl TimeControl {
+    /// Parses `5` (minutes), `5+3` (3 second increment) or `10+3x30`
+    /// (byo-yomi, three periods of 30 seconds).
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (base, overtime) = match s.split_once('+') {
+            Some((base, overtime)) => (base, Some(overtime)),
+            None => (s, None),
+        };
+        let base = parse_duration(base, 60.0)?;
+        let overtime = match overtime {
+            None => Overtime::None,
+            Some(overtime) => match overtime.split_once('x') {
+                Some((periods, period)) => Overtime::ByoYomi {
+                    period: parse_duration(period, 1.0)?,
+                    periods: periods
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("invalid number of periods '{}'", periods.trim()))?,
+                },
+                None => Overtime::Increment(parse_duration(overtime, 1.0)?),
+            },
+        };
+        match overtime {
+            Overtime::ByoYomi { period, periods } if period.is_zero() || periods == 0 => {
+                Err("byo-yomi needs at least one period of some time".to_string())
+            }
+            // The increment only comes after a move, too late for the first one.
+            Overtime::None | Overtime::Increment(_) if base.is_zero() => {
+                Err("a time control needs some time".to_string())
+            }
+            _ => Ok(Self { base, overtime }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SideClock {
+    main: Duration,
+    periods: u32,
+}
+
+/// How a side stands `elapsed` into its turn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Reading {
+    Main(Duration),
+    Period { left: Duration, periods: u32 },
+    Flagged,
+}
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    control: TimeControl,
+    black: SideClock,
+    white: SideClock,
+    running: Option<(Side, Instant)>,
+    paused: Option<Instant>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let periods = match control.overtime {
+            Overtime::ByoYomi { periods, .. } => periods,
+            _ => 0,
+        };
+        let side = SideClock {
+            main: control.base,
+            periods,
+        };
+        Self {
+            control,
+            black: side,
+            white: side,
+            running: None,
+            paused: None,
+        }
+    }
+
+    fn side(&self, side: Side) -> &SideClock {
+        if side == WHITE {
+            &self.white
+        } else {
+            &self.black
+        }
+    }
+
+    fn elapsed(&self, side: Side, now: Instant) -> Duration {
+        let now = self.paused.unwrap_or(now);
+        match self.running {
+            Some((running, since)) if running == side => now.saturating_duration_since(since),
+            _ => Duration::ZERO,
+        }
+    }
+
+    fn reading(&self, side: Side, now: Instant) -> Reading {
+        let clock = self.side(side);
+        let elapsed = self.elapsed(side, now);
+        if elapsed < clock.main {
+            return Reading::Main(clock.main - elapsed);
+        }
+        match self.control.overtime {
+            Overtime::ByoYomi { period, .. } if !period.is_zero() => {
+                let over = elapsed - clock.main;
+                let used = (over.as_secs_f64() / period.as_secs_f64()) as u32;
+                if used >= clock.periods {
+                    Reading::Flagged
+                } else {
+                    Reading::Period {
+                        left: period
+                            - Duration::from_secs_f64(over.as_secs_f64() % period.as_secs_f64()),
+                        periods: clock.periods - used,
+                    }
+                }
+            }
+            _ => Reading::Flagged,
+        }
+    }
+
+    /// Starts `side`'s clock; the other one is stopped first.
+    pub fn start(&mut self, side: Side, now: Instant) {
+        self.stop(now);
+        self.running = Some((side, now));
+    }
+
+    /// Stops the running clock after a move, applying increment or byo-yomi.
+    pub fn stop(&mut self, now: Instant) {
+        let (side, reading) = match self.running {
+            Some((side, _)) => (side, self.reading(side, now)),
+            None => return,
+        };
+        let control = self.control;
+        let clock = if side == WHITE {
+            &mut self.white
+        } else {
+            &mut self.black
+        };
+        match reading {
+            Reading::Main(left) => {
+                clock.main = left;
+                if let Overtime::Increment(inc) = control.overtime {
+                    clock.main += inc;
+                }
+            }
+            Reading::Period { periods, .. } => {
+                clock.main = Duration::ZERO;
+                clock.periods = periods;
+            }
+            Reading::Flagged => {
+                clock.main = Duration::ZERO;
+                clock.periods = 0;
+            }
+        }
+        self.running = None;
+    }
+
+    /// Freezes the running clock without ending the turn.
+    pub fn pause(&mut self, now: Instant) {
+        self.paused.get_or_insert(now);
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        if let (Some(paused), Some((side, since))) = (self.paused.take(), self.running) {
+            self.running = Some((side, since + now.saturating_duration_since(paused)));
+        }
+    }
+
+    /// The side whose flag has fallen, if any.
+    pub fn flagged(&self, now: Instant) -> Option<Side> {
+        [BLACK, WHITE]
+            .into_iter()
+            .find(|side| self.reading(*side, now) == Reading::Flagged)
+    }
+
+    pub fn display(&self, side: Side, now: Instant) -> String {
+        let fmt = |d: Duration| {
+            let secs = d.as_secs_f64().ceil() as u64;
+            format!("{}:{:02}", secs / 60, secs % 60)
+        };
+        match self.reading(side, now) {
+            Reading::Main(left) => fmt(left),
+            Reading::Period { left, periods } => format!("{} ({}x)", fmt(left), periods),
+            Reading::Flagged => "flag".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn parses_time_controls() {
+        assert_eq!(TimeControl::parse("5").unwrap().base, secs(300));
+        assert_eq!(
+            TimeControl::parse("3+2").unwrap().overtime,
+            Overtime::Increment(secs(2))
+        );
+        assert_eq!(
+            TimeControl::parse("10+3x30").unwrap().overtime,
+            Overtime::ByoYomi {
+                period: secs(30),
+                periods: 3
+            }
+        );
+        assert!(TimeControl::parse("0").is_err());
+        assert!(TimeControl::parse("0+5").is_err());
+        assert!(TimeControl::parse("5+0x30").is_err());
+        assert!(TimeControl::parse("5+2x0").is_err());
+        assert!(TimeControl::parse("inf").is_err());
+        assert!(TimeControl::parse("1e30").is_err());
+        assert!(TimeControl::parse("5+1e30").is_err());
+        assert!(TimeControl::parse("fast").is_err());
+    }
+
+    #[test]
+    fn increment_is_added_after_each_move() {
+        let mut clock = Clock::new(TimeControl::parse("1+5").unwrap());
+        let t0 = Instant::now();
+        clock.start(BLACK, t0);
+        assert_eq!(clock.display(BLACK, t0 + secs(20)), "0:40");
+        clock.start(WHITE, t0 + secs(20));
+        assert_eq!(clock.display(BLACK, t0 + secs(30)), "0:45");
+        assert_eq!(clock.flagged(t0 + secs(79)), None);
+        assert_eq!(clock.flagged(t0 + secs(81)), Some(WHITE));
+    }
+
+    #[test]
+    fn paused_time_is_not_counted() {
+        let mut clock = Clock::new(TimeControl::parse("1").unwrap());
+        let t0 = Instant::now();
+        clock.start(BLACK, t0);
+        clock.pause(t0 + secs(10));
+        assert_eq!(clock.display(BLACK, t0 + secs(100)), "0:50");
+        clock.resume(t0 + secs(100));
+        assert_eq!(clock.display(BLACK, t0 + secs(105)), "0:45");
+    }
+
+    #[test]
+    fn byo_yomi_periods_run_out() {
+        let mut clock = Clock::new(TimeControl::parse("0+2x10").unwrap());
+        let t0 = Instant::now();
+        clock.start(BLACK, t0);
+        assert_eq!(clock.display(BLACK, t0 + secs(4)), "0:06 (2x)");
+        clock.start(WHITE, t0 + secs(12));
+        assert_eq!(clock.display(BLACK, t0 + secs(12)), "0:10 (1x)");
+        clock.start(BLACK, t0 + secs(13));
+        assert_eq!(clock.flagged(t0 + secs(22)), None);
+        assert_eq!(clock.flagged(t0 + secs(24)), Some(BLACK));
+    }
+}
@@ -0,0 +1,410 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    custom_elements::{prompt, show_dialog, DialogBox, ListScreen},
+    menu::Return,
+    storage,
+    termin::{terminal_window::TerminalHandler, window::Position},
+};
+
+use super::{
+    position::{parse_square, square_name},
+    record::GameRecord,
+    replay::replay,
+    wthor,
+};
+
+const DB_FILE: &str = "database.jsonl";
+const MAX_RESULTS: usize = 5000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DbGame {
+    pub black: String,
+    pub white: String,
+    pub tournament: String,
+    pub year: u16,
+    pub black_score: u8,
+    pub moves: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct OpeningStat {
+    pub games: u32,
+    pub black_wins: u32,
+    pub draws: u32,
+    pub black_discs: u32,
+}
+
+/// What `import_wthor` added, and what it left out.
+#[derive(Debug, Default, PartialEq)]
+pub struct Imported {
+    pub games: usize,
+    /// Games the database already had, or that came twice.
+    pub duplicates: usize,
+    /// Records with moves off the board or illegal ones.
+    pub skipped: usize,
+}
+
+#[derive(Default)]
+pub struct Database {
+    pub games: Vec<DbGame>,
+}
+
+impl DbGame {
+    fn summary(&self) -> String {
+        format!(
+            "{} {} {}-{} {} ({})",
+            self.year,
+            self.black,
+            self.black_score,
+            64 - self.black_score,
+            self.white,
+            self.tournament
+        )
+    }
+}
+
+/// Rewrites a transcript with the symmetry of the start position that
+/// turns its first move into f5, so transposed openings are counted together.
+pub fn normalized(moves: &str) -> String {
+    let map: fn((usize, usize)) -> (usize, usize) = match &moves.get(0..2) {
+        Some("e6") => |(r, c)| (c, r),
+        Some("c4") => |(r, c)| (7 - r, 7 - c),
+        Some("d3") => |(r, c)| (7 - c, 7 - r),
+        _ => return moves.to_string(),
+    };
+    moves
+        .as_bytes()
+        .chunks(2)
+        .filter_map(|m| parse_square(std::str::from_utf8(m).ok()?))
+        .map(|sq| {
+            let (r, c) = map(sq);
+            square_name(r, c)
+        })
+        .collect()
+}
+
+impl Database {
+    pub fn load() -> Self {
+        let content = storage::data_path(DB_FILE)
+            .and_then(|p| fs::read_to_string(p).map_err(|e| e.to_string()))
+            .unwrap_or_default();
+        Self {
+            games: content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+        }
+    }
+
+    pub fn append(games: &[DbGame]) -> Result<(), String> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(storage::data_path(DB_FILE)?)
+            .map_err(|e| e.to_string())?;
+        let mut out = String::new();
+        for game in games {
+            out += &serde_json::to_string(game).map_err(|e| e.to_string())?;
+            out.push('\n');
+        }
+        file.write_all(out.as_bytes()).map_err(|e| e.to_string())
+    }
+
+    pub fn search_player(&self, query: &str) -> Vec<&DbGame> {
+        let query = query.trim().to_lowercase();
+        self.games
+            .iter()
+            .filter(|g| {
+                g.black.to_lowercase().contains(&query) || g.white.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    /// Counts the moves played after `prefix` (a normalized transcript).
+    pub fn opening_stats(&self, prefix: &str) -> BTreeMap<String, OpeningStat> {
+        let mut stats: BTreeMap<String, OpeningStat> = BTreeMap::new();
+        for game in &self.games {
+            let moves = normalized(&game.moves);
+            if !moves.starts_with(prefix) || moves.len() < prefix.len() + 2 {
+                continue;
+            }
+            let stat = stats
+                .entry(moves[prefix.len()..prefix.len() + 2].to_string())
+                .or_default();
+            stat.games += 1;
+            stat.black_discs += game.black_score as u32;
+            if game.black_score > 32 {
+                stat.black_wins += 1;
+            } else if game.black_score == 32 {
+                stat.draws += 1;
+            }
+        }
+        stats
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn read_names(
+    wtb: &Path,
+    ext: &str,
+    parse: fn(&[u8]) -> Result<Vec<String>, String>,
+) -> Vec<String> {
+    wthor::find_sibling(wtb, ext)
+        .and_then(|p| read(&p).ok())
+        .and_then(|b| parse(&b).ok())
+        .unwrap_or_default()
+}
+
+/// Reads one `.wtb` file, naming players and tournaments from the
+/// `.jou`/`.trn` files in the same directory when present. Also returns how
+/// many records could not be read.
+pub fn read_wthor(wtb: &Path) -> Result<(Vec<DbGame>, usize), String> {
+    let (header, games, skipped) = wthor::parse_games(&read(wtb)?)?;
+    let players = read_names(wtb, "jou", wthor::parse_players);
+    let tournaments = read_names(wtb, "trn", wthor::parse_tournaments);
+    let name = |names: &Vec<String>, idx: u16| {
+        names
+            .get(idx as usize)
+            .cloned()
+            .unwrap_or(format!("#{}", idx))
+    };
+
+    let db_games: Vec<DbGame> = games
+        .iter()
+        .filter_map(|g| {
+            Some(DbGame {
+                black: name(&players, g.black),
+                white: name(&players, g.white),
+                tournament: name(&tournaments, g.tournament),
+                year: header.year,
+                black_score: g.black_score,
+                moves: g.to_record().ok()?.to_transcript(),
+            })
+        })
+        .collect();
+    let skipped = skipped + games.len() - db_games.len();
+    Ok((db_games, skipped))
+}
+
+/// Imports a `.wtb` file, or every `.wtb` file of a directory, leaving out
+/// games the database already has.
+pub fn import_wthor(path: &Path) -> Result<Imported, String> {
+    let files: Vec<PathBuf> = if path.is_dir() {
+        fs::read_dir(path)
+            .map_err(|e| e.to_string())?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("wtb"))
+            })
+            .collect()
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut known: HashSet<DbGame> = Database::load().games.into_iter().collect();
+    let mut imported = Imported::default();
+    for file in files {
+        let (games, skipped) = read_wthor(&file)?;
+        let read = games.len();
+        let games: Vec<DbGame> = games
+            .into_iter()
+            .filter(|g| known.insert(g.clone()))
+            .collect();
+        Database::append(&games)?;
+        imported.games += games.len();
+        imported.duplicates += read - games.len();
+        imported.skipped += skipped;
+    }
+    Ok(imported)
+}
+
+pub fn import_screen<Ctx>(terminal: &mut TerminalHandler, _: &mut Ctx) -> Return {
+    let path = prompt(terminal, "Import WTHOR", "wtb file or folder: ", 36, 200);
+    if path.trim().is_empty() {
+        return Return::None;
+    }
+
+    let mut dbox = DialogBox::new(60, 5).position(terminal.root.rect(), Position::Coord(5, 5));
+    dbox.info("importing games...");
+    terminal.root.clear();
+    terminal.root.draw_element(&dbox);
+    terminal.refresh().unwrap();
+
+    match import_wthor(Path::new(path.trim())) {
+        Ok(imported) => dbox.info(&format!(
+            "imported {} games, {} already there, {} unreadable",
+            imported.games, imported.duplicates, imported.skipped
+        )),
+        Err(e) => dbox.error(&e),
+    }
+    show_dialog(terminal, &dbox);
+    Return::None
+}
+
+pub fn search_screen<Ctx>(terminal: &mut TerminalHandler, _: &mut Ctx) -> Return {
+    let query = prompt(terminal, "Search Player", "player: ", 30, 30);
+    let db = Database::load();
+    let games = db.search_player(&query);
+
+    let mut screen = ListScreen::new(terminal);
+    screen.list.set_items(
+        games
+            .iter()
+            .take(MAX_RESULTS)
+            .map(|g| g.summary())
+            .collect(),
+    );
+    let heading = format!(
+        "{} games with '{}'{}",
+        games.len(),
+        query.trim(),
+        if games.len() > MAX_RESULTS {
+            format!(", showing the first {}", MAX_RESULTS)
+        } else {
+            "".to_string()
+        }
+    );
+
+    loop {
+        screen.render(
+            terminal,
+            &heading,
+            "up/down: scroll  enter: replay  esc: back",
+        );
+        match terminal.getch() {
+            KeyCode::Esc => break,
+            KeyCode::Enter if !screen.list.is_empty() => {
+                let game = games[screen.list.cursor()];
+                if let Ok(mut record) = GameRecord::from_transcript(&game.moves) {
+                    record.black = game.black.clone();
+                    record.white = game.white.clone();
+                    replay(terminal, record);
+                }
+            }
+            k => screen.navigate(k),
+        }
+    }
+    screen.win.delete();
+    Return::None
+}
+
+pub fn openings_screen<Ctx>(terminal: &mut TerminalHandler, _: &mut Ctx) -> Return {
+    let db = Database::load();
+    let mut screen = ListScreen::new(terminal);
+    let mut prefix = String::new();
+    let mut moves: Vec<String> = vec![];
+
+    loop {
+        let stats = db.opening_stats(&prefix);
+        moves = stats.keys().cloned().collect();
+        screen.list.set_items(
+            stats
+                .iter()
+                .map(|(mv, s)| {
+                    format!(
+                        "{}  {:>7} games  black wins {:>5.1}%  draws {:>4.1}%  avg black discs {:.1}",
+                        mv,
+                        s.games,
+                        s.black_wins as f32 * 100.0 / s.games as f32,
+                        s.draws as f32 * 100.0 / s.games as f32,
+                        s.black_discs as f32 / s.games as f32
+                    )
+                })
+                .collect(),
+        );
+
+        loop {
+            let line = if prefix.is_empty() {
+                "start position".to_string()
+            } else {
+                prefix.clone()
+            };
+            screen.render(
+                terminal,
+                &format!("{} games in database, after: {}", db.games.len(), line),
+                "enter: play move  backspace: take back  esc: back",
+            );
+            match terminal.getch() {
+                KeyCode::Esc => {
+                    screen.win.delete();
+                    return Return::None;
+                }
+                KeyCode::Enter if !screen.list.is_empty() => {
+                    prefix += &moves[screen.list.cursor()];
+                    break;
+                }
+                KeyCode::Backspace if !prefix.is_empty() => {
+                    prefix.truncate(prefix.len() - 2);
+                    break;
+                }
+                k => screen.navigate(k),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(moves: &str, black_score: u8) -> DbGame {
+        DbGame {
+            black: "Tastet Marc".to_string(),
+            white: "Shaman Brian".to_string(),
+            tournament: "Paris".to_string(),
+            year: 1990,
+            black_score,
+            moves: moves.to_string(),
+        }
+    }
+
+    #[test]
+    fn openings_are_counted_up_to_symmetry() {
+        assert_eq!(normalized("d3c3"), "f5f6");
+        let db = Database {
+            games: vec![game("f5d6", 40), game("d3c5", 20), game("f5f6", 32)],
+        };
+
+        let stats = db.opening_stats("f5");
+        assert_eq!(stats["d6"].games, 2);
+        assert_eq!(stats["d6"].black_wins, 1);
+        assert_eq!(stats["f6"].draws, 1);
+        assert_eq!(db.search_player("shaman").len(), 3);
+    }
+
+    #[test]
+    fn imports_each_game_once() {
+        let _data = storage::temp_data_dir();
+        let mut wtb = vec![20, 24, 1, 15, 3, 0, 0, 0, 0, 0, 0xe7, 0x07, 8, 0, 22, 0];
+        for first in [56, 56, 11] {
+            wtb.extend([0; 8]);
+            let mut moves = vec![first];
+            moves.resize(60, 0);
+            wtb.extend(moves);
+        }
+        let path = storage::data_path("games.wtb").unwrap();
+        fs::write(&path, wtb).unwrap();
+
+        let imported = |games, duplicates| Imported {
+            games,
+            duplicates,
+            skipped: 1,
+        };
+        assert_eq!(import_wthor(&path), Ok(imported(1, 1)));
+        assert_eq!(import_wthor(&path), Ok(imported(0, 2)));
+        assert_eq!(Database::load().games.len(), 1);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::storage;
+
+use super::{
+    board::{BLACK, WHITE},
+    position::{parse_square, square_name, Position},
+    record::{GameRecord, Ply},
+};
+
+/// One game in Generic Game Format: `(;GM[Othello]PB[..]PW[..]BO[8 ...]B[f5]W[d6]...;)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GgfGame {
+    pub place: String,
+    pub date: String,
+    pub result: String,
+    pub time: String,
+    pub black_rating: String,
+    pub white_rating: String,
+    pub record: GameRecord,
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+/// Splits the inside of `(; ... ;)` into `(key, value)` pairs.
+fn properties(body: &str) -> Result<Vec<(String, String)>, String> {
+    let mut props = vec![];
+    let mut chars = body.chars().peekable();
+
+    loop {
+        while matches!(chars.peek(), Some(c) if c.is_whitespace() || *c == ';') {
+            chars.next();
+        }
+        let mut key = String::new();
+        while let Some(c) = chars.peek() {
+            if *c == '[' {
+                break;
+            }
+            key.push(*c);
+            chars.next();
+        }
+        let key = key.trim().to_string();
+        if chars.next().is_none() {
+            if key.is_empty() {
+                return Ok(props);
+            }
+            return Err(format!("property {} has no value", key));
+        }
+
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some('\\') => {
+                    if let Some(c) = chars.next() {
+                        value.push(c);
+                    }
+                }
+                Some(']') => break,
+                Some(c) => value.push(c),
+                None => return Err(format!("unterminated value for {}", key)),
+            }
+        }
+        props.push((key, value));
+    }
+}
+
+fn parse_board(value: &str) -> Result<Position, String> {
+    let mut parts = value.trim().splitn(2, char::is_whitespace);
+    if parts.next() != Some("8") {
+        return Err("only 8x8 boards are supported".to_string());
+    }
+    Position::parse(parts.next().unwrap_or(""))
+}
+
+fn write_board(pos: &Position) -> String {
+    let mut s = "8".to_string();
+    for row in pos.board {
+        s.push(' ');
+        for cell in row {
+            s.push(match cell {
+                BLACK => '*',
+                WHITE => 'O',
+                _ => '-',
+            });
+        }
+    }
+    s.push(' ');
+    s.push(if pos.side == WHITE { 'O' } else { '*' });
+    s
+}
+
+impl GgfGame {
+    pub fn from_record(record: GameRecord) -> Self {
+        let (year, month, day, hour, minute, second) = storage::civil_time(storage::timestamp());
+        let current = record.current();
+        let result = if current.legal_moves_for(BLACK).is_empty()
+            && current.legal_moves_for(WHITE).is_empty()
+        {
+            format!(
+                "{:+.3}",
+                current.count(BLACK) as f32 - current.count(WHITE) as f32
+            )
+        } else {
+            String::new()
+        };
+
+        Self {
+            place: "othello-rs".to_string(),
+            date: format!(
+                "{}.{:02}.{:02}_{:02}:{:02}:{:02}.GMT",
+                year, month, day, hour, minute, second
+            ),
+            result,
+            time: String::new(),
+            black_rating: String::new(),
+            white_rating: String::new(),
+            record,
+        }
+    }
+
+    pub fn parse(game: &str) -> Result<Self, String> {
+        let body = game
+            .trim()
+            .strip_prefix("(;")
+            .and_then(|s| s.strip_suffix(";)"))
+            .ok_or("a GGF game must be enclosed in (; ;)")?;
+
+        let mut game = Self::from_record(GameRecord::new(Position::initial()));
+        game.place.clear();
+        game.date.clear();
+        let (mut black, mut white) = (String::new(), String::new());
+
+        for (key, value) in properties(body)? {
+            match key.as_str() {
+                "GM" if !value.eq_ignore_ascii_case("othello") => {
+                    return Err(format!("not an Othello game: {}", value));
+                }
+                "PC" => game.place = value,
+                "DT" => game.date = value,
+                "PB" => black = value,
+                "PW" => white = value,
+                "RB" => game.black_rating = value,
+                "RW" => game.white_rating = value,
+                "RE" => game.result = value,
+                "TI" => game.time = value,
+                "TY" if !value.trim_start_matches(['s', 'S']).starts_with('8') => {
+                    return Err(format!("unsupported game type: {}", value));
+                }
+                "BO" => {
+                    if !game.record.plies.is_empty() {
+                        return Err("BO must come before the moves".to_string());
+                    }
+                    game.record = GameRecord::new(parse_board(&value)?);
+                }
+                "B" | "W" => {
+                    let side = if key == "B" { BLACK } else { WHITE };
+                    let mv = value.split('/').next().unwrap_or("").trim().to_lowercase();
+                    if mv == "pa" || mv == "pass" {
+                        game.record.pass();
+                        continue;
+                    }
+                    let square = parse_square(&mv).ok_or(format!("invalid move '{}'", value))?;
+                    if !game.record.play(side, square) {
+                        return Err(format!(
+                            "illegal move {} at move {}",
+                            mv,
+                            game.record.plies.len() + 1
+                        ));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        game.record.black = black;
+        game.record.white = white;
+        Ok(game)
+    }
+
+    /// Parses every game in a GGF file, which may hold several back to back.
+    pub fn parse_all(content: &str) -> Result<Vec<Self>, String> {
+        let mut games = vec![];
+        let mut rest = content;
+
+        while let Some(start) = rest.find("(;") {
+            let end = rest[start..].find(";)").ok_or("unterminated GGF game")? + start + 2;
+            games.push(Self::parse(&rest[start..end])?);
+            rest = &rest[end..];
+        }
+        Ok(games)
+    }
+
+    pub fn to_ggf(&self) -> String {
+        let mut s = "(;GM[Othello]".to_string();
+        let mut prop = |key: &str, value: &str| {
+            if !value.is_empty() {
+                s += &format!("{}[{}]", key, escape(value));
+            }
+        };
+
+        prop("PC", &self.place);
+        prop("DT", &self.date);
+        prop("PB", &self.record.black);
+        prop("PW", &self.record.white);
+        prop("RB", &self.black_rating);
+        prop("RW", &self.white_rating);
+        prop("TI", &self.time);
+        prop("TY", "8");
+        prop("RE", &self.result);
+        prop("BO", &write_board(&self.record.start));
+
+        let mut pos = self.record.start;
+        for ply in &self.record.plies {
+            let key = if pos.side == WHITE { "W" } else { "B" };
+            match ply {
+                Ply::Move((row, col)) => {
+                    s += &format!("{}[{}]", key, square_name(*row, *col));
+                    pos.play(*row, *col);
+                }
+                Ply::Pass => {
+                    s += &format!("{}[PA]", key);
+                    pos.pass();
+                }
+            }
+        }
+        s + ";)"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_players_result_and_moves() {
+        let game = GgfGame::parse(
+            "(;GM[Othello]PC[NIOC]DT[2003.12.15_13:24:03.GMT]PB[alice]PW[bob]RB[2000]RW[1900]\
+             TI[05:00//02:00]TY[8]RE[+2.000]\
+             BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]\
+             B[f5//0.01]W[d6/1.5/0.2]B[c3];)",
+        )
+        .unwrap();
+
+        assert_eq!(game.record.black, "alice");
+        assert_eq!(game.record.white, "bob");
+        assert_eq!(game.result, "+2.000");
+        assert_eq!(game.time, "05:00//02:00");
+        assert_eq!(game.record.to_transcript(), "f5d6c3");
+    }
+
+    #[test]
+    fn saved_games_round_trip() {
+        let mut record = GameRecord::from_transcript("f5d6c3d3c4f4f6").unwrap();
+        record.black = "Player".to_string();
+        record.white = "Bot ]".to_string();
+        let game = GgfGame::from_record(record);
+
+        let parsed = GgfGame::parse(&game.to_ggf()).unwrap();
+        assert_eq!(parsed, game);
+        assert_eq!(
+            GgfGame::parse_all(&(game.to_ggf() + "\n" + &game.to_ggf()))
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn rejects_other_games() {
+        assert!(GgfGame::parse("(;GM[Go]BO[8 *];)").is_err());
+        assert!(GgfGame::parse("(;GM[Othello]B[a1];)").is_err());
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+};
+
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    custom_elements::{prompt, ListScreen},
+    menu::Return,
+    storage,
+    termin::terminal_window::TerminalHandler,
+};
+
+use super::{
+    board::{Side, BLACK, WHITE},
+    position::{opponent_of, Position},
+    record::GameRecord,
+    replay::replay,
+};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Offline,
+    Online,
+}
+
+/// How a game was decided when it was not by counting discs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Ending {
+    /// Lost on time, by resigning or by leaving.
+    Lost(Side),
+    /// Drawn by agreement.
+    Draw,
+    /// Cut off by our own connection before there was a result.
+    Unfinished,
+}
+
+/// Reads `ending`, or the side in the `loser` field older files have.
+fn ending_or_loser<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Ending>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Ending(Ending),
+        Loser(Side),
+    }
+    Ok(match Option::<Stored>::deserialize(d)? {
+        Some(Stored::Ending(ending)) => Some(ending),
+        Some(Stored::Loser(side)) => Some(Ending::Lost(side)),
+        None => None,
+    })
+}
+
+/// One finished game, as kept in `history.jsonl`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub date: u64,
+    pub mode: Mode,
+    pub black: String,
+    pub white: String,
+    /// The side the local player had, `None` when both sides were played here.
+    pub my_side: Option<Side>,
+    pub black_discs: u8,
+    pub white_discs: u8,
+    /// Why the game ended early, if it did (resignation, disconnect...).
+    pub note: String,
+    #[serde(default, alias = "loser", deserialize_with = "ending_or_loser")]
+    pub ending: Option<Ending>,
+    pub start: String,
+    pub moves: String,
+}
+
+impl HistoryEntry {
+    pub fn new(mode: Mode, my_side: Option<Side>, record: &GameRecord, note: &str) -> Self {
+        let current = record.current();
+        Self {
+            date: storage::timestamp(),
+            mode,
+            black: record.black.clone(),
+            white: record.white.clone(),
+            my_side,
+            black_discs: current.count(BLACK),
+            white_discs: current.count(WHITE),
+            note: note.to_string(),
+            ending: None,
+            start: record.start.to_string(),
+            moves: record.to_transcript(),
+        }
+    }
+
+    pub fn record(&self) -> Result<GameRecord, String> {
+        let mut record =
+            GameRecord::from_transcript_at(Position::parse(&self.start)?, &self.moves)?;
+        record.black = self.black.clone();
+        record.white = self.white.clone();
+        Ok(record)
+    }
+
+    pub fn winner(&self) -> Option<Side> {
+        match self.ending {
+            Some(Ending::Lost(loser)) => Some(opponent_of(loser)),
+            Some(Ending::Draw | Ending::Unfinished) => None,
+            None if self.black_discs > self.white_discs => Some(BLACK),
+            None if self.white_discs > self.black_discs => Some(WHITE),
+            None => None,
+        }
+    }
+
+    pub fn result(&self) -> String {
+        let winner = match (self.ending, self.winner()) {
+            (Some(Ending::Unfinished), _) => "no result",
+            (_, Some(BLACK)) => "black won",
+            (_, Some(_)) => "white won",
+            (_, None) => "draw",
+        };
+        let mut s = format!("{} {}-{}", winner, self.black_discs, self.white_discs);
+        if !self.note.is_empty() {
+            s += &format!(" ({})", self.note);
+        }
+        s
+    }
+
+    pub fn summary(&self) -> String {
+        let (year, month, day, hour, minute, _) = storage::civil_time(self.date);
+        format!(
+            "{}-{:02}-{:02} {:02}:{:02}  {:<7} {} vs {}  {}",
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            match self.mode {
+                Mode::Offline => "offline",
+                Mode::Online => "online",
+            },
+            self.black,
+            self.white,
+            self.result()
+        )
+    }
+}
+
+/// Appends `entry` to the history file.
+pub fn append(entry: &HistoryEntry) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(storage::data_path(HISTORY_FILE)?)
+        .map_err(|e| e.to_string())?;
+    let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+/// Every stored game, oldest first.
+pub fn load() -> Vec<HistoryEntry> {
+    storage::data_path(HISTORY_FILE)
+        .and_then(|p| fs::read_to_string(p).map_err(|e| e.to_string()))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn matches(entry: &HistoryEntry, filter: &str) -> bool {
+    let filter = filter.trim().to_lowercase();
+    filter.is_empty() || entry.summary().to_lowercase().contains(&filter)
+}
+
+pub fn history_screen<Ctx>(terminal: &mut TerminalHandler, _: &mut Ctx) -> Return {
+    let mut entries = load();
+    entries.reverse();
+    let mut screen = ListScreen::new(terminal);
+    let mut filter = String::new();
+
+    loop {
+        let shown: Vec<&HistoryEntry> = entries.iter().filter(|e| matches(e, &filter)).collect();
+        screen
+            .list
+            .set_items(shown.iter().map(|e| e.summary()).collect());
+
+        loop {
+            let heading = if filter.trim().is_empty() {
+                format!("{} games played", entries.len())
+            } else {
+                format!(
+                    "{} of {} games match '{}'",
+                    shown.len(),
+                    entries.len(),
+                    filter.trim()
+                )
+            };
+            screen.render(
+                terminal,
+                &heading,
+                "up/down: scroll  enter: replay  f: filter  esc: back",
+            );
+            match terminal.getch() {
+                KeyCode::Esc => {
+                    screen.win.delete();
+                    return Return::None;
+                }
+                KeyCode::Enter if !screen.list.is_empty() => {
+                    if let Ok(record) = shown[screen.list.cursor()].record() {
+                        replay(terminal, record);
+                    }
+                }
+                KeyCode::Char('f') | KeyCode::Char('/') => {
+                    filter = prompt(
+                        terminal,
+                        "Filter History",
+                        "name, mode, date or result: ",
+                        24,
+                        40,
+                    );
+                    break;
+                }
+                k => screen.navigate(k),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_keep_the_game_and_its_result() {
+        let mut record = GameRecord::from_transcript("f5d6c3d3c4").unwrap();
+        record.black = "alice".to_string();
+        record.white = "Bot".to_string();
+        let entry = HistoryEntry::new(Mode::Offline, Some(BLACK), &record, "");
+
+        let line = serde_json::to_string(&entry).unwrap();
+        let parsed: HistoryEntry = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.record().unwrap(), record);
+        assert_eq!(parsed.result(), "black won 6-3");
+        assert!(matches(&parsed, "ALICE"));
+        assert!(!matches(&parsed, "online"));
+    }
+
+    #[test]
+    fn reads_the_loser_of_older_entries() {
+        let record = GameRecord::from_transcript("f5").unwrap();
+        let entry = HistoryEntry::new(Mode::Offline, None, &record, "");
+        let mut line = serde_json::to_value(&entry).unwrap();
+        line.as_object_mut().unwrap().remove("ending");
+        line["loser"] = serde_json::json!("b");
+        let parsed: HistoryEntry = serde_json::from_value(line).unwrap();
+        assert_eq!(parsed.ending, Some(Ending::Lost(BLACK)));
+        assert_eq!(parsed.winner(), Some(WHITE));
+
+        let mut unfinished = entry.clone();
+        unfinished.ending = Some(Ending::Unfinished);
+        let line = serde_json::to_string(&unfinished).unwrap();
+        let parsed: HistoryEntry = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.ending, Some(Ending::Unfinished));
+        assert_eq!(parsed.result(), "no result 4-1");
+    }
+}
@@ -0,0 +1,232 @@
+use super::position::Square;
+
+/// The biggest bordered cells `fit` tries, in rows per square.
+const MAX_CELL_HEIGHT: u32 = 5;
+
+/// Where the squares of the board go inside `board_container`. Every piece
+/// of code turning squares into screen cells or back goes through here.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    /// Size of one square, in terminal cells.
+    pub cell: (u32, u32),
+    /// Columns between the left edges of two neighbouring squares, and rows
+    /// between their tops.
+    step: (u32, u32),
+    /// Where `board_win` sits inside `board_container`.
+    offset: (u32, u32),
+    /// Lines are drawn in the one cell gap between squares.
+    pub borders: bool,
+}
+
+impl Layout {
+    /// One character per square, for tiny terminals.
+    pub fn compact() -> Self {
+        Self {
+            cell: (1, 1),
+            step: (2, 1),
+            offset: (2, 1),
+            borders: false,
+        }
+    }
+
+    /// `2x1` squares, the size that fits an 80x24 terminal.
+    pub fn normal() -> Self {
+        Self {
+            cell: (2, 1),
+            step: (4, 2),
+            offset: (2, 1),
+            borders: false,
+        }
+    }
+
+    /// Squares `height` rows tall and twice as wide, with a grid around them.
+    pub fn bordered(height: u32) -> Self {
+        Self {
+            cell: (height * 2, height),
+            step: (height * 2 + 1, height + 1),
+            offset: (3, 2),
+            borders: true,
+        }
+    }
+
+    /// The biggest layout whose `size` fits in `width` x `height`, falling
+    /// back to `compact` when even that doesn't.
+    pub fn fit(width: u32, height: u32) -> Self {
+        (2..=MAX_CELL_HEIGHT)
+            .rev()
+            .map(Self::bordered)
+            .chain([Self::normal()])
+            .find(|layout| {
+                let (w, h) = layout.size();
+                w <= width && h <= height
+            })
+            .unwrap_or_else(Self::compact)
+    }
+
+    fn gap(&self) -> (u32, u32) {
+        (self.step.0 - self.cell.0, self.step.1 - self.cell.1)
+    }
+
+    /// Size of `board_win`.
+    pub fn board_size(&self) -> (u32, u32) {
+        let (gap_x, gap_y) = self.gap();
+        (self.step.0 * 8 - gap_x, self.step.1 * 8 - gap_y)
+    }
+
+    /// Size of `board_container`: the squares plus room for the labels.
+    pub fn size(&self) -> (u32, u32) {
+        let (width, height) = self.board_size();
+        (width + self.offset.0 * 2, height + self.offset.1 * 2)
+    }
+
+    pub fn offset(&self) -> (u32, u32) {
+        self.offset
+    }
+
+    /// Top left corner of square `(row, col)` inside `board_win`.
+    pub fn cell_xy(&self, row: usize, col: usize) -> (u32, u32) {
+        (col as u32 * self.step.0, row as u32 * self.step.1)
+    }
+
+    /// Top left corner of square `(row, col)` inside `board_container`.
+    pub fn container_xy(&self, row: usize, col: usize) -> (u32, u32) {
+        let (x, y) = self.cell_xy(row, col);
+        (x + self.offset.0, y + self.offset.1)
+    }
+
+    /// Middle of a square relative to its top left corner, where glyphs and
+    /// labels go.
+    pub fn center(&self) -> (u32, u32) {
+        ((self.cell.0 - 1) / 2, (self.cell.1 - 1) / 2)
+    }
+
+    /// Square under `(x, y)` inside `board_container`. The gap to the right
+    /// of and below a square counts as part of it.
+    pub fn square_at(&self, x: u32, y: u32) -> Option<Square> {
+        let (x, y) = (x.checked_sub(self.offset.0)?, y.checked_sub(self.offset.1)?);
+        let (row, col) = ((y / self.step.1) as usize, (x / self.step.0) as usize);
+        (row < 8 && col < 8).then_some((row, col))
+    }
+
+    /// The a-h and 1-8 labels around the squares, as `(x, y, label)` inside
+    /// `board_container`: letters above and below each column, numbers left
+    /// and right of each row.
+    pub fn label_positions(&self) -> Vec<(u32, u32, char)> {
+        let (width, height) = self.size();
+        let (dx, dy) = self.center();
+        let mut labels = vec![];
+        for idx in 0..8 {
+            let (x, y) = self.container_xy(idx, idx);
+            let letter = (b'a' + idx as u8) as char;
+            let number = (b'1' + idx as u8) as char;
+            labels.push((x + dx, 0, letter));
+            labels.push((x + dx, height - 1, letter));
+            labels.push((0, y + dy, number));
+            labels.push((width - 1, y + dy, number));
+        }
+        labels
+    }
+
+    /// The grid of a bordered layout as `(x, y, text)` inside
+    /// `board_container`: a full line for each edge between rows, single
+    /// bars for the edges between columns. Empty without borders.
+    pub fn grid_lines(&self) -> Vec<(u32, u32, String)> {
+        if !self.borders {
+            return vec![];
+        }
+        let (left, top) = (self.offset.0 - 1, self.offset.1 - 1);
+        let (step_x, step_y) = self.step;
+        let mut lines = vec![];
+        for row in 0..=8 * step_y {
+            if row % step_y != 0 {
+                for col in 0..=8 {
+                    lines.push((left + col * step_x, top + row, "│".to_string()));
+                }
+                continue;
+            }
+            let corners = match row / step_y {
+                0 => ['┌', '┬', '┐'],
+                8 => ['└', '┴', '┘'],
+                _ => ['├', '┼', '┤'],
+            };
+            let line = (0..=8 * step_x)
+                .map(|x| match (x % step_x, x / step_x) {
+                    (0, 0) => corners[0],
+                    (0, 8) => corners[2],
+                    (0, _) => corners[1],
+                    _ => '─',
+                })
+                .collect();
+            lines.push((left, top + row, line));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_at_inverts_container_xy() {
+        let layout = Layout::normal();
+        for (row, col) in [(0, 0), (3, 5), (7, 7)] {
+            let (x, y) = layout.container_xy(row, col);
+            assert_eq!(layout.square_at(x, y), Some((row, col)));
+            assert_eq!(layout.square_at(x + 3, y + 1), Some((row, col)));
+        }
+        assert_eq!(layout.square_at(1, 5), None);
+        assert_eq!(layout.square_at(2, 0), None);
+        assert_eq!(layout.square_at(34, 5), None);
+    }
+
+    #[test]
+    fn labels_line_up_with_the_squares() {
+        let layout = Layout::normal();
+        let labels = layout.label_positions();
+        assert_eq!(labels.len(), 32);
+        let (x, y) = layout.container_xy(5, 3);
+        let (width, height) = layout.size();
+        assert!(labels.contains(&(x, 0, 'd')));
+        assert!(labels.contains(&(x, height - 1, 'd')));
+        assert!(labels.contains(&(0, y, '6')));
+        assert!(labels.contains(&(width - 1, y, '6')));
+    }
+
+    #[test]
+    fn fit_picks_the_biggest_layout_that_fits() {
+        assert_eq!(Layout::normal().size(), (34, 17));
+        assert_eq!(Layout::compact().size(), (19, 10));
+        assert_eq!(Layout::fit(76, 17), Layout::normal());
+        assert_eq!(Layout::fit(40, 12), Layout::compact());
+        assert_eq!(Layout::fit(200, 100), Layout::bordered(MAX_CELL_HEIGHT));
+
+        let layout = Layout::fit(60, 30);
+        assert_eq!(layout, Layout::bordered(2));
+        let (width, height) = layout.size();
+        assert!(width <= 60 && height <= 30);
+        for (x, y, _) in layout.label_positions() {
+            assert!(x < width && y < height);
+        }
+        for (row, col) in [(0, 0), (7, 7)] {
+            let (x, y) = layout.container_xy(row, col);
+            assert_eq!(layout.square_at(x + 3, y + 1), Some((row, col)));
+        }
+    }
+
+    #[test]
+    fn grid_surrounds_every_square() {
+        let layout = Layout::bordered(2);
+        let grid = layout.grid_lines();
+        let (x, y) = layout.container_xy(0, 0);
+        let top = format!("┌{}┐", ["────"; 8].join("┬"));
+        assert!(grid.contains(&(x - 1, y - 1, top)));
+        assert!(grid.contains(&(x - 1, y, "│".to_string())));
+        assert!(grid.contains(&(x + 4, y + 1, "│".to_string())));
+        let (width, height) = layout.size();
+        assert!(grid
+            .iter()
+            .all(|(x, y, _)| *x < width - 1 && *y < height - 1));
+        assert!(Layout::normal().grid_lines().is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+//! A stand-in for othellojs on localhost, so `Online` and `OnlineGame` can be
+//! driven by tests. The websocket of the game plays a script of `Step`s.
+
+use std::{
+    io::{self, Write},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crossterm::event::KeyCode;
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use serde_json::json;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    runtime::Runtime,
+};
+use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+
+use super::{
+    board::{Side, BLACK, EMPTY, WHITE},
+    position::Position,
+    socket::SocketMsg,
+};
+use crate::{
+    server::http,
+    servers::Server,
+    termin::{
+        crossterm_handler::{CrosstermHandler, ScriptedInput},
+        recorder::Recorder,
+        terminal_window::{Terminal, TerminalHandler},
+        window::Window,
+    },
+};
+
+pub const GAME_ID: &str = "mock-game";
+
+pub enum Step {
+    /// Waits for the client to emit an event, logging everything it sends.
+    Expect(&'static str),
+    /// Emits an event with `data` exactly as it goes over the wire.
+    Send(&'static str, String),
+    /// Sends a frame as is, to feed the client something malformed.
+    Raw(String),
+    Delay(Duration),
+    /// Presses a key on the client once everything before it has gone out.
+    Press(KeyCode),
+    /// Goes away without a close frame. Later steps still run.
+    Drop,
+}
+
+/// Emits `event` with `data` encoded the way othellojs does it.
+pub fn send<T: Serialize>(event: &'static str, data: &T) -> Step {
+    Step::Send(event, serde_json::to_string(data).unwrap())
+}
+
+/// What othellojs says from the opening of the socket to the start of the
+/// game, seating the client on `side`.
+pub fn lobby(side: Side, black: &str, white: &str) -> Vec<Step> {
+    vec![
+        send("game-verified", &true),
+        Step::Expect("join-player-info"),
+        send(
+            "join-player-info-res",
+            &json!({"err": false, "msg": "", "playerId": "p1", "side": side as u8}),
+        ),
+        send("lobby-info", &json!({"black": black, "white": white})),
+        send("countdown-begin", &""),
+        Step::Expect("game-state"),
+    ]
+}
+
+/// The answer to `game-state` for `pos`.
+pub fn game_state(pos: &Position) -> Step {
+    let board = pos
+        .board
+        .map(|row| row.map(|s| if s == EMPTY { 0 } else { s as u8 }));
+    send(
+        "game-state-res",
+        &json!({
+            "board": board,
+            "curTurn": pos.side as u8,
+            "blackPoints": pos.count(BLACK),
+            "whitePoints": pos.count(WHITE),
+            "isOpponentOnline": true,
+        }),
+    )
+}
+
+/// A terminal as big as a roomy window that reads `input` and throws its
+/// output away.
+pub fn terminal(input: &ScriptedInput) -> TerminalHandler {
+    let out: Box<dyn Write> = Box::new(io::sink());
+    let handler = CrosstermHandler::new(Recorder::new(out)).with_input(input.clone());
+    Terminal::new(Window::default().size(120, 40), handler)
+}
+
+struct State {
+    script: Mutex<Option<Vec<Step>>>,
+    input: ScriptedInput,
+    log: Mutex<Vec<(String, String)>>,
+}
+
+pub struct MockServer {
+    pub server: Server,
+    state: Arc<State>,
+    _runtime: Runtime,
+}
+
+impl MockServer {
+    /// Listens on a free port. The first websocket to join plays `script`,
+    /// pressing keys into `input`.
+    pub fn start(script: Vec<Step>, input: &ScriptedInput) -> Self {
+        let runtime = Runtime::new().unwrap();
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let addr: SocketAddr = listener.local_addr().unwrap();
+        let state = Arc::new(State {
+            script: Mutex::new(Some(script)),
+            input: input.clone(),
+            log: Mutex::new(vec![]),
+        });
+
+        let shared = state.clone();
+        runtime.spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, shared.clone()));
+            }
+        });
+
+        Self {
+            server: Server {
+                name: "mock".to_string(),
+                url: format!("http://{}", addr),
+            },
+            state,
+            _runtime: runtime,
+        }
+    }
+
+    /// Everything the client sent, as `(event, data)` for socket events and
+    /// `(method path, body)` for http requests.
+    pub fn log(&self) -> Vec<(String, String)> {
+        self.state.log.lock().unwrap().clone()
+    }
+}
+
+async fn serve(mut stream: TcpStream, state: Arc<State>) {
+    let (method, path) = http::request_line(&stream).await;
+    if path.starts_with("/api/join-game/") {
+        if let Ok(socket) = tokio_tungstenite::accept_async(stream).await {
+            let script = state.script.lock().unwrap().take().unwrap_or_default();
+            play(socket, script, &state).await;
+        }
+        return;
+    }
+
+    let request = format!("{} {}", method, path);
+    let body = http::read_body(&mut stream).await;
+    let response = match path.strip_prefix("/api/game-info/") {
+        Some(GAME_ID) => {
+            json!({"err": false, "msg": "", "lobbyName": "mock", "isLobbyFull": false})
+        }
+        Some(_) => json!({"err": true, "msg": "game not found"}),
+        None if request == "POST /api/create-lobby" => {
+            json!({"err": false, "msg": "", "gameId": GAME_ID})
+        }
+        None => json!({"err": true, "msg": "unknown route"}),
+    };
+    state.log.lock().unwrap().push((request, body));
+    http::reply(&mut stream, "200 OK", &response).await;
+}
+
+async fn play(socket: WebSocketStream<TcpStream>, script: Vec<Step>, state: &State) {
+    let log = |text: &str| {
+        let entry = match SocketMsg::from(text.to_string()) {
+            Ok(msg) => (msg.name, msg.data),
+            Err(_) => (String::new(), text.to_string()),
+        };
+        state.log.lock().unwrap().push(entry.clone());
+        entry.0
+    };
+
+    let mut socket = Some(socket);
+    for step in script {
+        match (step, socket.as_mut()) {
+            (Step::Expect(event), Some(ws)) => loop {
+                match ws.next().await {
+                    Some(Ok(Message::Text(text))) if log(&text) == event => break,
+                    Some(Ok(_)) => (),
+                    _ => {
+                        socket = None;
+                        break;
+                    }
+                }
+            },
+            (Step::Send(event, data), Some(ws)) => {
+                let msg = json!({"name": event, "data": data}).to_string();
+                _ = ws.send(Message::Text(msg)).await;
+            }
+            (Step::Raw(text), Some(ws)) => _ = ws.send(Message::Text(text)).await,
+            (Step::Delay(time), _) => tokio::time::sleep(time).await,
+            (Step::Press(key), _) => state.input.key(key),
+            (Step::Drop, _) => socket = None,
+            (_, None) => (),
+        }
+    }
+
+    if let Some(mut ws) = socket {
+        while let Some(Ok(msg)) = ws.next().await {
+            if let Message::Text(text) = msg {
+                log(&text);
+            }
+        }
+    }
+}
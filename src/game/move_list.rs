@@ -0,0 +1,164 @@
+use crossterm::style::Color;
+
+use crate::{
+    termin::{
+        elements::Text,
+        window::{Position, Window, WindowRef},
+    },
+    theme::Theme,
+};
+
+use super::{
+    board::WHITE,
+    position::square_name,
+    record::{GameRecord, Ply},
+};
+
+pub const WIDTH: u32 = 14;
+
+/// One entry per ply, laid out two to a row (black, then white). A game
+/// started with white to move gets an empty first slot.
+pub fn slots(record: &GameRecord) -> Vec<Option<String>> {
+    let mut slots = vec![];
+    if record.start.side == WHITE {
+        slots.push(None);
+    }
+    for ply in &record.plies {
+        slots.push(Some(match ply {
+            Ply::Move((row, col)) => square_name(*row, *col),
+            Ply::Pass => "pa".to_string(),
+        }));
+    }
+    slots
+}
+
+/// Numbered list of the moves of a game, drawn into a scrolling window
+/// that follows the current ply until it is scrolled by hand.
+#[derive(Debug)]
+pub struct MoveList {
+    win: WindowRef,
+    highlight: Color,
+    /// Number of slots and the ply of the last `render`.
+    shown: Option<(usize, usize)>,
+}
+
+impl MoveList {
+    pub fn new(parent: &mut WindowRef, x: u32, y: u32, height: u32) -> Self {
+        Self {
+            win: parent.new_child(
+                Window::default()
+                    .size(WIDTH, height)
+                    .xy(x, y)
+                    .scoll_size(WIDTH, height),
+            ),
+            highlight: Theme::load().highlight,
+            shown: None,
+        }
+    }
+
+    /// Draws the list with the move leading to `ply` highlighted, scrolled
+    /// to it when the game or the ply changed since the last call.
+    pub fn render(&mut self, record: &GameRecord, ply: usize) {
+        let slots = slots(record);
+        let offset = slots.len() - record.plies.len();
+        let current = (ply + offset).checked_sub(1);
+        let height = self.win.height() as usize;
+        let rows = slots.len().div_ceil(2);
+        let top = match current {
+            _ if self.shown == Some((slots.len(), ply)) => self.win.scroll().y as usize,
+            Some(idx) if idx / 2 >= height => idx / 2 + 1 - height,
+            _ => 0,
+        };
+        self.shown = Some((slots.len(), ply));
+
+        self.win.set_scroll_xy(0, 0);
+        self.win.set_scroll_size(WIDTH, rows.max(height) as u32);
+        for (row, pair) in slots.chunks(2).enumerate() {
+            let y = row as u32;
+            let mut text = Text::default()
+                .text(&format!("{:>2}.", row + 1))
+                .size(3, 1)
+                .xy(0, y);
+            self.win.draw_element(&text);
+
+            for (idx, slot) in pair.iter().enumerate() {
+                if let Some(name) = slot {
+                    text.set_text(name);
+                    text.set_size(2, 1);
+                    text.set_xy(4 + 4 * idx as u32, y);
+                    text.set_fg(if current == Some(row * 2 + idx) {
+                        self.highlight
+                    } else {
+                        Color::Reset
+                    });
+                    self.win.draw_element(&text);
+                }
+            }
+        }
+        self.win.set_scroll_xy(0, top as u32);
+        self.win.render();
+    }
+
+    /// Scrolls by `dy` rows, staying within the list.
+    pub fn scroll(&mut self, dy: i16) {
+        self.win.set_scroll_xy_rel(0, dy);
+        self.win.render();
+    }
+
+    /// Moves the panel to `(x, y)` with room for `height` rows; the next
+    /// `render` scrolls to the current ply again.
+    pub fn place(&mut self, x: u32, y: u32, height: u32) {
+        self.shown = None;
+        self.win.set_size(WIDTH, height);
+        self.win.set_position(Position::Coord(x, y));
+    }
+
+    /// Blanks the panel; the next `render` scrolls to the current ply again.
+    pub fn hide(&mut self) {
+        self.shown = None;
+        self.win.set_scroll_xy(0, 0);
+        self.win.clear();
+        self.win.render();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::position::Position;
+    use crate::storage;
+
+    #[test]
+    fn white_to_move_leaves_the_first_slot_empty() {
+        let record = GameRecord::from_transcript("f5d6").unwrap();
+        assert_eq!(
+            slots(&record),
+            vec![Some("f5".to_string()), Some("d6".to_string())]
+        );
+
+        let mut start = Position::initial();
+        start.pass();
+        let record = GameRecord::from_transcript_at(start, "f4").unwrap();
+        assert_eq!(slots(&record), vec![None, Some("f4".to_string())]);
+    }
+
+    #[test]
+    fn follows_the_current_ply_until_scrolled() {
+        let _data = storage::temp_data_dir();
+        let record = GameRecord::from_transcript("f5d6c3d3c4f4f6f3e6e7").unwrap();
+        let mut root = WindowRef::new(20, 10, 0, 0);
+        let mut list = MoveList::new(&mut root, 0, 0, 3);
+
+        list.render(&record, 10);
+        assert_eq!(list.win.scroll().y, 2);
+        list.scroll(-1);
+        list.render(&record, 10);
+        assert_eq!(list.win.scroll().y, 1);
+        list.scroll(-5);
+        assert_eq!(list.win.scroll().y, 0);
+        list.render(&record, 2);
+        assert_eq!(list.win.scroll().y, 0);
+        list.render(&record, 9);
+        assert_eq!(list.win.scroll().y, 2);
+    }
+}
@@ -1,16 +1,24 @@
 use rand::Rng;
 
 use crate::{
+    custom_elements::{overlay_menu, prompt, show_dialog, DialogBox},
     game::macros::choose_side_win,
     menu::Return,
     sleep,
     termin::{
         terminal_window::TerminalHandler,
-        window::{draw_elements, Window},
+        window::{Position, Window, WindowRef},
     },
 };
 
-use super::{board::WHITE, Game};
+use super::{
+    board::{Side, BLACK, WHITE},
+    clock::{Clock, TimeControl},
+    history::{self, Ending, HistoryEntry, Mode},
+    position::opponent_of,
+    record::GameRecord,
+    search, CursorResult, Game,
+};
 
 #[derive(Copy, Clone)]
 pub enum ParticipantType {
@@ -18,9 +26,31 @@ pub enum ParticipantType {
     Player,
 }
 
+/// Plays the second side of a two player game.
+const PLAYER_2: &str = "Player 2";
+
 pub struct Offline {
     pub black: ParticipantType,
     pub white: ParticipantType,
+    /// Names of the black and white players, used unless the record has some.
+    pub names: (String, String),
+    pub time_control: Option<TimeControl>,
+}
+
+/// What became of the game after the pause menu.
+enum Paused {
+    Resume,
+    Ended(Ending),
+    /// Left without finishing; nothing goes into the history.
+    Left,
+}
+
+fn side_name(side: Side) -> &'static str {
+    if side == WHITE {
+        "White"
+    } else {
+        "Black"
+    }
 }
 
 fn rand_item_from_vec<T: Copy>(v: &Vec<T>) -> T {
@@ -30,23 +60,70 @@ fn rand_item_from_vec<T: Copy>(v: &Vec<T>) -> T {
 
 impl Offline {
     pub fn begin_game(&self, terminal: &mut TerminalHandler) {
+        self.play(terminal, None);
+    }
+
+    pub fn resume_game(&self, terminal: &mut TerminalHandler, record: GameRecord) {
+        self.play(terminal, Some(record));
+    }
+
+    fn play(&self, terminal: &mut TerminalHandler, record: Option<GameRecord>) {
         terminal.clear();
         let mut offline_win = terminal
             .root
             .new_child(Window::default().size(terminal.root.width(), terminal.root.height()));
         let mut game = Game::new(&mut offline_win);
-        let mut cur_turn = self.black;
 
-        game.init_board();
+        match record {
+            Some(record) => game.load_record(record),
+            None => game.init_board(),
+        }
+        if game.record.black.is_empty() {
+            game.record.black = self.names.0.clone();
+        }
+        if game.record.white.is_empty() {
+            game.record.white = self.names.1.clone();
+        }
+        game.clock = self.time_control.map(Clock::new);
+        let mut cur_turn = if game.cur_turn_side == WHITE {
+            self.white
+        } else {
+            self.black
+        };
+        game.check_is_over();
         game.render_cur_turn_side();
 
-        while !game.is_over {
+        let mut ending = None;
+        'game: while !game.is_over {
             match cur_turn {
                 ParticipantType::Player => {
                     game.board.calc_available_moves(game.cur_turn_side);
                     if !game.board.available_moves.is_empty() {
                         game.board.place_cursor_on_legal_position();
-                        game.enable_cursor_movement(terminal);
+                        game.start_clock();
+                        loop {
+                            match game.enable_cursor_movement(terminal) {
+                                CursorResult::Played => break,
+                                CursorResult::Flagged => break 'game,
+                                CursorResult::Cancelled => {
+                                    game.pause_clock();
+                                    let paused = self.pause(terminal, &mut game, &mut offline_win);
+                                    game.resume_clock();
+                                    match paused {
+                                        Paused::Resume => (),
+                                        Paused::Ended(e) => {
+                                            ending = Some(e);
+                                            break 'game;
+                                        }
+                                        Paused::Left => {
+                                            offline_win.delete();
+                                            return;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        game.stop_clock();
                     } else {
                         sleep(1000);
                     }
@@ -72,7 +149,12 @@ impl Offline {
 
                         game.board.move_cursor(rand_col as u16, *rand_row as u16);
 
+                        game.start_clock();
                         sleep(2000);
+                        if game.flagged().is_some() {
+                            break;
+                        }
+                        game.stop_clock();
                         game.play_move();
                     } else {
                         sleep(1000);
@@ -87,22 +169,127 @@ impl Offline {
                 }
             }
             game.render_cur_turn_side();
+            game.render_clock();
             game.check_is_over();
+            game.play_animation(terminal);
             game.render_board();
             terminal.refresh().unwrap();
         }
+        let ending = ending.or(game.flagged().map(Ending::Lost));
+        game.stop_clock();
+        game.render_clock();
 
-        game.render_game_over(&mut offline_win, "");
-
-        terminal.refresh().unwrap();
+        let my_side = match (self.black, self.white) {
+            (ParticipantType::Player, ParticipantType::Bot) => Some(BLACK),
+            (ParticipantType::Bot, ParticipantType::Player) => Some(WHITE),
+            _ => None,
+        };
+        let note = match ending {
+            Some(Ending::Lost(side)) if game.flagged() == Some(side) => {
+                format!("{} lost on time", side_name(side))
+            }
+            Some(Ending::Lost(side)) => format!("{} resigned", side_name(side)),
+            Some(Ending::Draw) => "Draw agreed".to_string(),
+            Some(Ending::Unfinished) | None => String::new(),
+        };
+        let mut entry =
+            HistoryEntry::new(Mode::Offline, my_side, &game.record, &note.to_lowercase());
+        entry.ending = ending;
+        _ = history::append(&entry);
 
-        terminal.getch();
+        game.render_game_over(&mut offline_win, &note);
+        game.offer_replay(terminal);
 
         offline_win.delete();
     }
+
+    fn participant(&self, side: Side) -> ParticipantType {
+        if side == WHITE {
+            self.white
+        } else {
+            self.black
+        }
+    }
+
+    /// Opened with Esc during a player's turn, with the clock already paused.
+    fn pause(
+        &self,
+        terminal: &mut TerminalHandler,
+        game: &mut Game,
+        win: &mut WindowRef,
+    ) -> Paused {
+        let side = game.cur_turn_side;
+        let items = [
+            "resume",
+            "save game and quit",
+            "resign",
+            "offer draw",
+            "quit without saving",
+        ];
+        let paused = match overlay_menu(terminal, win, "Paused", &items) {
+            Some(1) => {
+                let mut dbox =
+                    DialogBox::new(35, 5).position(terminal.root.rect(), Position::Coord(5, 5));
+                match game.record.save() {
+                    Ok(path) => {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        dbox.info(&format!("saved as {}", name));
+                        show_dialog(terminal, &dbox);
+                        Paused::Left
+                    }
+                    Err(e) => {
+                        dbox.error(&format!("unable to save: {}", e));
+                        show_dialog(terminal, &dbox);
+                        Paused::Resume
+                    }
+                }
+            }
+            Some(2) => match overlay_menu(terminal, win, "Resign?", &["no", "yes"]) {
+                Some(1) => Paused::Ended(Ending::Lost(side)),
+                _ => Paused::Resume,
+            },
+            Some(3) => {
+                if self.draw_accepted(terminal, game, win) {
+                    Paused::Ended(Ending::Draw)
+                } else {
+                    game.set_status("draw declined");
+                    Paused::Resume
+                }
+            }
+            Some(4) => Paused::Left,
+            _ => Paused::Resume,
+        };
+        game.render_board();
+        terminal.refresh().unwrap();
+        paused
+    }
+
+    /// A bot takes the draw unless it thinks it is winning; a player is asked.
+    fn draw_accepted(
+        &self,
+        terminal: &mut TerminalHandler,
+        game: &mut Game,
+        win: &mut WindowRef,
+    ) -> bool {
+        let opponent = opponent_of(game.cur_turn_side);
+        match self.participant(opponent) {
+            ParticipantType::Bot => {
+                let pos = game.position();
+                // scored for the side offering the draw
+                search::best_move(&pos, search::default_depth(&pos)).map_or(0, |(_, score)| score)
+                    >= 0
+            }
+            ParticipantType::Player => {
+                let heading = format!("{}, accept a draw?", side_name(opponent));
+                overlay_menu(terminal, win, &heading, &["no", "yes"]) == Some(1)
+            }
+        }
+    }
 }
 
-pub fn play_offline<Ctx>(terminal: &mut TerminalHandler, _: &mut Ctx, no_of_players: u8) -> Return {
+/// Starts a game against the bot, or between two players sharing the
+/// terminal. `name` is the player who picks the side.
+pub fn play_offline(terminal: &mut TerminalHandler, name: &str, no_of_players: u8) -> Return {
     let cur_side = choose_side_win!(
         terminal,
         "Play Offline",
@@ -113,27 +300,87 @@ pub fn play_offline<Ctx>(terminal: &mut TerminalHandler, _: &mut Ctx, no_of_play
         }
     );
 
+    let time_control = match ask_time_control(terminal) {
+        Ok(time_control) => time_control,
+        Err(e) => {
+            let mut dbox =
+                DialogBox::new(35, 5).position(terminal.root.rect(), Position::Coord(5, 5));
+            dbox.error(&e);
+            show_dialog(terminal, &dbox);
+            return Return::None;
+        }
+    };
+
     use crate::game::offline_game::ParticipantType::{Bot, Player};
 
-    if no_of_players == 2 {
-        Offline {
-            black: Player,
-            white: Player,
-        }
+    let (black, white) = if no_of_players == 2 {
+        (Player, Player)
+    } else if cur_side == WHITE {
+        (Bot, Player)
     } else {
-        if cur_side == 'w' {
-            Offline {
-                black: Bot,
-                white: Player,
-            }
-        } else {
-            Offline {
-                black: Player,
-                white: Bot,
-            }
-        }
+        (Player, Bot)
+    };
+    let other = if no_of_players == 2 { PLAYER_2 } else { "Bot" };
+    let names = if cur_side == WHITE {
+        (other.to_string(), name.to_string())
+    } else {
+        (name.to_string(), other.to_string())
+    };
+    Offline {
+        black,
+        white,
+        names,
+        time_control,
     }
     .begin_game(terminal);
 
     Return::ToRoot
 }
+
+/// Asks for a time control such as `5+3`; a blank answer means untimed.
+fn ask_time_control(terminal: &mut TerminalHandler) -> Result<Option<TimeControl>, String> {
+    let input = prompt(
+        terminal,
+        "Time Control",
+        "minutes[+increment | +NxS byo-yomi], blank for none: ",
+        10,
+        12,
+    );
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+    TimeControl::parse(&input).map(Some)
+}
+
+pub fn load_offline(terminal: &mut TerminalHandler, name: &str) -> Return {
+    let input = prompt(
+        terminal,
+        "Load Game",
+        "transcript, position or file: ",
+        40,
+        144,
+    );
+    if input.trim().is_empty() {
+        return Return::None;
+    }
+
+    match GameRecord::load(&input) {
+        Ok(record) => {
+            Offline {
+                black: ParticipantType::Player,
+                white: ParticipantType::Player,
+                names: (name.to_string(), PLAYER_2.to_string()),
+                time_control: None,
+            }
+            .resume_game(terminal, record);
+            Return::ToRoot
+        }
+        Err(e) => {
+            let mut dbox =
+                DialogBox::new(35, 5).position(terminal.root.rect(), Position::Coord(5, 5));
+            dbox.error(&e);
+            show_dialog(terminal, &dbox);
+            Return::None
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 use std::io::Error;
 
-use crossterm::event::{Event, EventStream, KeyCode};
+use crossterm::event::{Event, KeyCode, MouseEventKind};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use tokio::select;
@@ -11,6 +11,8 @@ use tokio_tungstenite::tungstenite::Message;
 use super::{
     board::{Side, WHITE},
     chat::ChatSection,
+    history::{self, Ending, HistoryEntry, Mode},
+    position::opponent_of,
     socket::{emit, SocketMsg, WS},
     Game,
 };
@@ -23,10 +25,13 @@ use crate::{
     },
 };
 
+const KEYS_HELP: &str = "d3: move  tab: chat  x: diagram  s: save  m: moves";
+
 pub struct OnlineGame<'a> {
     chat: ChatSection,
     game: Game,
     my_side: Side,
+    players: (String, String),
     is_cur_turn: bool,
     is_opponent_online: bool,
     online_win: WindowRef,
@@ -69,11 +74,12 @@ struct ReconnInfo {
 }
 
 impl<'a> OnlineGame<'a> {
-    pub fn new(opponent_name: String, my_side: Side, terminal: &'a mut TerminalHandler) -> Self {
+    pub fn new(black_name: String, white_name: String, my_side: Side, terminal: &'a mut TerminalHandler) -> Self {
         let mut online_win = terminal
             .root
             .new_child(Window::default().size(terminal.root.width(), terminal.root.height()));
-        let game = Game::new(&mut online_win);
+        let mut game = Game::new(&mut online_win);
+        game.set_keys_help(KEYS_HELP);
         let reconn_info = ReconnInfo{
             is_waiting: false,
             cur_wait_time: 20,
@@ -83,9 +89,11 @@ impl<'a> OnlineGame<'a> {
                 .xy(0, 0)
             )
         };
+        let opponent_name = if my_side == WHITE { &black_name } else { &white_name };
         Self {
-            chat: ChatSection::new(&mut online_win, opponent_name),
+            chat: ChatSection::new(&mut online_win, opponent_name.clone(), game.footer_xy()),
             my_side,
+            players: (black_name, white_name),
             is_opponent_online: false,
             is_cur_turn: false,
             online_win,
@@ -110,32 +118,12 @@ impl<'a> OnlineGame<'a> {
             }
         }
 
-        loop {
-            let msg = socket.next().await.unwrap();
+        while let Some(msg) = socket.next().await {
             match msg {
                 Ok(msg) => match msg {
                     Message::Text(t) => {
-                        let msg = SocketMsg::from(t);
-                        if msg.event_name() == "game-state-res" {
-                            #[derive(Deserialize)]
-                            struct GameStateRes {
-                                board: [[u8; 8]; 8],
-                                curTurn: u8,
-                                blackPoints: u8,
-                                whitePoints: u8,
-                                isOpponentOnline: bool,
-                            }
-                            let data: GameStateRes = msg.parse();
-
-                            let board = data.board.map(|row| {
-                                row.map(|cell| if cell == 0 { EMPTY } else { cell as Side })
-                            });
-
-                            self.game.board.board = board;
-                            self.game.board.black_points = data.blackPoints;
-                            self.game.board.white_points = data.whitePoints;
-                            self.is_cur_turn = data.curTurn as char == self.my_side;
-                            self.is_opponent_online = data.isOpponentOnline;
+                        let Ok(msg) = SocketMsg::from(t) else { continue };
+                        if msg.event_name() == "game-state-res" && self.load_game_state(&msg) {
                             return;
                         }
                     }
@@ -153,6 +141,45 @@ impl<'a> OnlineGame<'a> {
     }
 
 
+    /// Puts the board of a `game-state-res` in front of the player. Returns
+    /// false when the message doesn't hold one.
+    fn load_game_state(&mut self, msg: &SocketMsg) -> bool {
+        #[derive(Deserialize)]
+        struct GameStateRes {
+            board: [[u8; 8]; 8],
+            curTurn: u8,
+            blackPoints: u8,
+            whitePoints: u8,
+            isOpponentOnline: bool,
+        }
+        let Ok(data) = msg.parse::<GameStateRes>() else { return false };
+
+        let board = data.board.map(|row| {
+            row.map(|cell| if cell == 0 { EMPTY } else { cell as Side })
+        });
+
+        let pos = super::position::Position {
+            board,
+            side: data.curTurn as Side,
+        };
+        // Keep the moves that led here when the board is one we went through.
+        let mut record = self.game.record.clone();
+        while record.current().board != pos.board && record.plies.pop().is_some() {}
+        if record.current().board == pos.board {
+            self.game.load_record(record);
+            self.game.set_cur_turn_side(pos.side);
+        } else {
+            self.game.load_position(&pos);
+        }
+        self.game.record.black = self.players.0.clone();
+        self.game.record.white = self.players.1.clone();
+        self.game.board.black_points = data.blackPoints;
+        self.game.board.white_points = data.whitePoints;
+        self.is_cur_turn = data.curTurn as char == self.my_side;
+        self.is_opponent_online = data.isOpponentOnline;
+        true
+    }
+
     fn to_keycode(&self, e: Option<Result<Event, Error>>) -> Option<KeyCode> {
         match e {
             Some(e) => match e {
@@ -168,6 +195,8 @@ impl<'a> OnlineGame<'a> {
 
     fn set_cur_turn_true(&mut self) {
         self.is_cur_turn = true;
+        // The server says whose turn it is, which covers passes too.
+        self.game.set_cur_turn_side(self.my_side);
         self.game.render_cursor = true;
         self.game.render_available_moves = true;
         self.game.board.calc_available_moves(self.my_side);
@@ -196,16 +225,32 @@ impl<'a> OnlineGame<'a> {
                 GameStatus::RefreshTerminal
             }
             "opponent-move" => {
-                let opponent_move: MoveDetails = msg.parse();
-                self.game.board.move_cursor(opponent_move.colIdx, opponent_move.rowIdx);
-                self.play_move_local();
-                self.set_window_mode(WindowMode::GameMode);
+                if let Ok(opponent_move) = msg.parse::<MoveDetails>() {
+                    self.game.board.move_cursor(opponent_move.colIdx, opponent_move.rowIdx);
+                    self.game.set_cur_turn_side(opponent_of(self.my_side));
+                    self.play_move_local();
+                    self.set_window_mode(WindowMode::GameMode);
+                }
+                GameStatus::Continue
+            }
+            // The server refused our last move and sent its own board.
+            "game-state-res" if self.load_game_state(&msg) => {
+                if self.is_cur_turn {
+                    self.set_cur_turn_true();
+                } else {
+                    self.set_cur_turn_false();
+                }
+                self.game.set_status("the server refused that move");
+                self.set_window_mode(self.cur_window_mode);
                 GameStatus::Continue
             }
             "game-over" => GameStatus::GameOver(msg.data),
-            "chat-msg" => {
-                self.chat.receive(msg.parse());
-                GameStatus::ChatMsg
+            "chat-msg" => match msg.parse() {
+                Ok(text) => {
+                    self.chat.receive(text);
+                    GameStatus::ChatMsg
+                }
+                Err(_) => GameStatus::Continue,
             },
             "wait-for-opponent-reconnect" => GameStatus::WaitForReconnect,
             "opponent-reconnect" => GameStatus::OpponentReconnect,
@@ -213,7 +258,33 @@ impl<'a> OnlineGame<'a> {
         }
     }
 
+    /// Draws the next frame of the flip animation, returning when the one
+    /// after it is due. The chat covers the board, so it just ends there.
+    fn animation_frame(&mut self) -> Option<std::time::Duration> {
+        if let WindowMode::ChatMode = self.cur_window_mode {
+            self.game.stop_animation();
+            return None;
+        }
+        let next = self.game.animation_frame();
+        self.terminal.refresh().unwrap();
+        next
+    }
+
+    fn save_history(&self, note: &str, ending: Option<Ending>) {
+        let mut entry = HistoryEntry::new(Mode::Online, Some(self.my_side), &self.game.record, note);
+        entry.ending = ending;
+        _ = history::append(&entry);
+    }
+
     pub fn handle_game_over(&mut self, msg: String) {
+        let ending = match msg.as_str() {
+            "opponent left." | "connection was closed." => {
+                Some(Ending::Lost(opponent_of(self.my_side)))
+            }
+            "connection lost." => Some(Ending::Unfinished),
+            _ => None,
+        };
+        self.save_history(&msg, ending);
         if msg != "" {
             self.game.render_game_over(&mut self.online_win, &msg);
         } else {
@@ -276,12 +347,12 @@ impl<'a> OnlineGame<'a> {
                 self.chat.render();
                 self.reconn_info.win.render();
                 self.terminal.refresh().unwrap();
-                self.chat.enable_cursor();
+                self.chat.enable_cursor(&mut self.terminal.handler);
                 self.terminal.flush().unwrap();
                 self.cur_window_mode = ChatMode;
             },
             GameMode => {
-                self.chat.disable_cursor();
+                self.chat.disable_cursor(&mut self.terminal.handler);
                 self.terminal.clear();
                 self.game.render_board();
                 self.game.render_cur_turn_side();
@@ -299,28 +370,53 @@ impl<'a> OnlineGame<'a> {
         socket: &mut WS,
     ) -> bool {
         let mut dbox = DialogBox::new(35, 5).position(self.terminal.root.rect(), Position::Coord(5, 5));
+        if let Some(Ok(Event::Resize(width, height))) = e {
+            self.game.resize(self.terminal, width, height);
+            let (x, y) = self.game.footer_xy();
+            self.chat.recent_chat.set_position(Position::Coord(x, y));
+            self.set_window_mode(self.cur_window_mode);
+            return false;
+        }
         match self.cur_window_mode {
             WindowMode::GameMode => {
-                if let Some(k) = self.to_keycode(e) {
-                    match k {
-                        KeyCode::Char('c') => {
-                            self.set_window_mode(WindowMode::ChatMode);
-                        },
-                        KeyCode::Enter => {
-                            if self.is_cur_turn {
-                                self.play_move(socket, &mut dbox).await;
-                            }
-                        },
-                        KeyCode::Esc => {
-                            socket.close(None).await.unwrap();
-                            return true
-                        },
-                        _ => {
-                            self.game.keyboard_event(k);
+                if let Some(Ok(Event::Mouse(m))) = e {
+                    if self.is_cur_turn {
+                        if self.game.point_cursor(m) && self.game.cursor_move_is_legal() {
+                            self.play_move(socket, &mut dbox).await;
+                        } else {
                             self.game.render_board();
                             self.terminal.refresh().unwrap();
                         }
                     }
+                } else if let Some(k) = self.to_keycode(e) {
+                    match self.game.coordinate_key(k) {
+                        Some(true) => self.play_move(socket, &mut dbox).await,
+                        Some(false) => self.terminal.refresh().unwrap(),
+                        None => match k {
+                            KeyCode::Tab => {
+                                self.set_window_mode(WindowMode::ChatMode);
+                            },
+                            KeyCode::Enter => {
+                                if self.is_cur_turn {
+                                    if self.game.cursor_move_is_legal() {
+                                        self.play_move(socket, &mut dbox).await;
+                                    } else {
+                                        self.terminal.refresh().unwrap();
+                                    }
+                                }
+                            },
+                            KeyCode::Esc => {
+                                self.save_history("left the game", Some(Ending::Lost(self.my_side)));
+                                socket.close(None).await.unwrap();
+                                return true
+                            },
+                            _ => {
+                                self.game.keyboard_event(k);
+                                self.game.render_board();
+                                self.terminal.refresh().unwrap();
+                            }
+                        },
+                    }
                 }
             },
 
@@ -343,7 +439,7 @@ impl<'a> OnlineGame<'a> {
                                             }
                                         }
                                     }
-                                    self.chat.clear_input_win();
+                                    self.chat.clear_input_win(&mut self.terminal.handler);
                                     self.terminal.draw_window(&self.chat.chat_msgs).unwrap();
                                     self.terminal.draw_window(self.chat.input_win.input_win()).unwrap();
                                 },
@@ -364,9 +460,20 @@ impl<'a> OnlineGame<'a> {
                                     self.terminal.draw_window(self.chat.input_win.input_win()).unwrap();
                                 }
                             },
+                            Event::Mouse(m) => match m.kind {
+                                MouseEventKind::ScrollDown => {
+                                    self.chat.scroll_down();
+                                    self.terminal.draw_window(&self.chat.chat_msgs).unwrap();
+                                },
+                                MouseEventKind::ScrollUp => {
+                                    self.chat.scroll_up();
+                                    self.terminal.draw_window(&self.chat.chat_msgs).unwrap();
+                                },
+                                _ => ()
+                            },
                             _ => ()
                         }
-                        self.chat.input_win.update_cursor();
+                        self.chat.input_win.update_cursor(&mut self.terminal.handler);
                         self.terminal.flush().unwrap();
                     }
                 }
@@ -383,19 +490,19 @@ impl<'a> OnlineGame<'a> {
             Some(maybe_msg) => match maybe_msg {
                 Ok(msg) => match msg {
                     Message::Text(msg) => {
-                        match self.handle_socket_msg(SocketMsg::from(msg)) {
+                        let Ok(msg) = SocketMsg::from(msg) else { return false };
+                        match self.handle_socket_msg(msg) {
                             GameStatus::GameOver(msg) => {
                                 self.handle_game_over(msg);
-                                self.terminal.refresh().unwrap();
-                                self.terminal.getch();
+                                self.game.offer_replay(self.terminal);
                                 return true
                             },
                             GameStatus::ChatMsg => {
                                 if let WindowMode::ChatMode = self.cur_window_mode {
                                     self.terminal.draw_window(&self.chat.chat_msgs).unwrap();
-                                    self.terminal.flush().unwrap();
                                     self.chat.input_win.update_rel_xy();
-                                    self.chat.input_win.update_cursor();
+                                    self.chat.input_win.update_cursor(&mut self.terminal.handler);
+                                    self.terminal.flush().unwrap();
                                 } else {
                                     self.chat.recent_chat.render();
                                     self.terminal.refresh().unwrap();
@@ -408,9 +515,9 @@ impl<'a> OnlineGame<'a> {
                                     self.chat.chat_msgs.render_to_parent();
                                     self.chat.chat_section.render();
                                     self.terminal.draw_window(&self.chat.chat_section).unwrap();
-                                    self.terminal.flush().unwrap();
                                     self.chat.input_win.update_rel_xy();
-                                    self.chat.input_win.update_cursor();
+                                    self.chat.input_win.update_cursor(&mut self.terminal.handler);
+                                    self.terminal.flush().unwrap();
                                 }
                                 self.reconn_info.is_waiting = true;
                             },
@@ -418,9 +525,9 @@ impl<'a> OnlineGame<'a> {
                                 self.chat.set_recvr_is_online(true);
                                 if let WindowMode::ChatMode = self.cur_window_mode {
                                     self.terminal.draw_window(&self.chat.chat_section).unwrap();
-                                    self.terminal.flush().unwrap();
                                     self.chat.input_win.update_rel_xy();
-                                    self.chat.input_win.update_cursor();
+                                    self.chat.input_win.update_cursor(&mut self.terminal.handler);
+                                    self.terminal.flush().unwrap();
                                 }
                                 self.reconn_info.is_waiting = false;
                                 self.reconn_info.cur_wait_time = 20;
@@ -438,14 +545,20 @@ impl<'a> OnlineGame<'a> {
                     }
                     _ => ()
                 },
-                Err(_) => ()
+                Err(_) => return self.lost_connection(),
             },
-            None => ()
+            None => return self.lost_connection(),
         }
 
         return false
     }
 
+    fn lost_connection(&mut self) -> bool {
+        self.handle_game_over("connection lost.".to_string());
+        self.game.offer_replay(self.terminal);
+        true
+    }
+
     pub async fn begin_game(&mut self, mut socket: WS) {
         self.chat.set_recvr_is_online(true);
         let mut dbox = DialogBox::new(35, 5).position(self.terminal.root.rect(), Position::Coord(5, 5));
@@ -465,12 +578,14 @@ impl<'a> OnlineGame<'a> {
         self.game.render_cur_turn_side();
         self.terminal.refresh().unwrap();
 
-        let mut event = EventStream::new();
+        let mut event = self.terminal.event_stream();
 
         use tokio::time::{self, Instant, Duration};
 
         let timer = time::sleep(Duration::from_millis(1000));
         tokio::pin!(timer);
+        let frame = time::sleep(Duration::ZERO);
+        tokio::pin!(frame);
 
         while !self.game.is_over {
             if self.reconn_info.is_waiting {
@@ -488,10 +603,11 @@ impl<'a> OnlineGame<'a> {
 
                         if self.reconn_info.cur_wait_time == -10 {
                             self.handle_game_over("connection was closed.".to_string());
+                            self.game.offer_replay(self.terminal);
                             break
                         }
                         if let WindowMode::ChatMode = self.cur_window_mode {
-                            self.chat.input_win.update_cursor();
+                            self.chat.input_win.update_cursor(&mut self.terminal.handler);
                             self.terminal.flush().unwrap();
                         }
                         self.reconn_info.cur_wait_time -= 1;
@@ -507,6 +623,11 @@ impl<'a> OnlineGame<'a> {
                             break
                         }
                     },
+                    () = &mut frame, if self.game.is_animating() => {
+                        if let Some(delay) = self.animation_frame() {
+                            frame.as_mut().reset(Instant::now() + delay);
+                        }
+                    },
                 };
             } else {
                 select! {
@@ -519,6 +640,11 @@ impl<'a> OnlineGame<'a> {
                         if self.handle_socket_ev(socket_ev) {
                             break
                         }
+                    },
+                    () = &mut frame, if self.game.is_animating() => {
+                        if let Some(delay) = self.animation_frame() {
+                            frame.as_mut().reset(Instant::now() + delay);
+                        }
                     }
                 };
             }
@@ -527,3 +653,182 @@ impl<'a> OnlineGame<'a> {
         self.online_win.delete();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+    use tokio::runtime::Runtime;
+
+    use super::*;
+    use crate::game::{
+        board::BLACK,
+        history,
+        mock_server::{self, send, MockServer, Step},
+        position::Position,
+    };
+    use crate::storage;
+    use crate::termin::crossterm_handler::ScriptedInput;
+
+    #[test]
+    fn ignores_malformed_messages_and_ends_when_the_connection_drops() {
+        let _data = storage::temp_data_dir();
+        let input = ScriptedInput::default();
+        let script = vec![
+            Step::Expect("game-state"),
+            Step::Delay(Duration::from_millis(200)),
+            mock_server::game_state(&Position::initial()),
+            Step::Raw("{oops".to_string()),
+            Step::Send("opponent-move", "not a move".to_string()),
+            send("opponent-move", &json!({"rowIdx": 4, "colIdx": 5})),
+            send("chat-msg", &"hi"),
+            send("cur-turn", &""),
+            Step::Delay(Duration::from_millis(100)),
+            Step::Press(KeyCode::Char('f')),
+            Step::Press(KeyCode::Char('6')),
+            Step::Expect("move"),
+            Step::Drop,
+            Step::Delay(Duration::from_millis(300)),
+            Step::Press(KeyCode::Esc),
+        ];
+        let mock = MockServer::start(script, &input);
+        let mut terminal = mock_server::terminal(&input);
+
+        Runtime::new().unwrap().block_on(async {
+            let url = mock.server.ws("/api/join-game/x");
+            let (socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+            OnlineGame::new("Bob".to_string(), "Cy".to_string(), WHITE, &mut terminal)
+                .begin_game(socket)
+                .await;
+        });
+
+        let log = mock.log();
+        assert!(log.contains(&("move".to_string(), r#"{"rowIdx":5,"colIdx":5}"#.to_string())));
+        let game = history::load()
+            .into_iter()
+            .find(|e| e.white == "Cy")
+            .unwrap();
+        assert_eq!(game.note, "connection lost.");
+        assert_eq!(game.ending, Some(Ending::Unfinished));
+        assert_eq!(game.moves, "f5f6");
+    }
+
+    #[test]
+    fn takes_back_a_move_the_server_refused() {
+        let _data = storage::temp_data_dir();
+        let input = ScriptedInput::default();
+        let script = vec![
+            Step::Expect("game-state"),
+            mock_server::game_state(&Position::initial()),
+            send("cur-turn", &""),
+            Step::Delay(Duration::from_millis(100)),
+            Step::Press(KeyCode::Char('d')),
+            Step::Press(KeyCode::Char('3')),
+            Step::Expect("move"),
+            mock_server::game_state(&Position::initial()),
+            Step::Delay(Duration::from_millis(100)),
+            Step::Drop,
+            Step::Delay(Duration::from_millis(300)),
+            Step::Press(KeyCode::Esc),
+        ];
+        let mock = MockServer::start(script, &input);
+        let mut terminal = mock_server::terminal(&input);
+
+        Runtime::new().unwrap().block_on(async {
+            let url = mock.server.ws("/api/join-game/x");
+            let (socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+            OnlineGame::new("Dee".to_string(), "Eve".to_string(), BLACK, &mut terminal)
+                .begin_game(socket)
+                .await;
+        });
+
+        let game = history::load()
+            .into_iter()
+            .find(|e| e.black == "Dee")
+            .unwrap();
+        assert_eq!(game.moves, "");
+        assert_eq!((game.black_discs, game.white_discs), (2, 2));
+    }
+
+    /// Black takes c1, white has no reply, black takes c8.
+    fn white_passes() -> Position {
+        let mut board = "XO------".to_string();
+        board.push_str(&"-".repeat(48));
+        board.push_str("XO------X");
+        Position::parse(&board).unwrap()
+    }
+
+    fn play_out(script: Vec<Step>, black: &str, white: &str, me: Side) -> HistoryEntry {
+        let _data = storage::temp_data_dir();
+        let input = ScriptedInput::default();
+        let mock = MockServer::start(script, &input);
+        let mut terminal = mock_server::terminal(&input);
+
+        Runtime::new().unwrap().block_on(async {
+            let url = mock.server.ws("/api/join-game/x");
+            let (socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+            OnlineGame::new(black.to_string(), white.to_string(), me, &mut terminal)
+                .begin_game(socket)
+                .await;
+        });
+        history::load()
+            .into_iter()
+            .find(|e| e.black == black)
+            .unwrap()
+    }
+
+    #[test]
+    fn follows_the_opponent_through_our_pass() {
+        let script = vec![
+            Step::Expect("game-state"),
+            mock_server::game_state(&white_passes()),
+            send("opponent-move", &json!({"rowIdx": 0, "colIdx": 2})),
+            send("opponent-move", &json!({"rowIdx": 7, "colIdx": 2})),
+            Step::Send("game-over", "opponent left.".to_string()),
+            Step::Delay(Duration::from_millis(300)),
+            Step::Press(KeyCode::Esc),
+        ];
+        let game = play_out(script, "Fay", "Gus", WHITE);
+        assert_eq!(game.moves, "c1c8");
+        assert_eq!((game.black_discs, game.white_discs), (6, 0));
+        assert_eq!(game.winner(), Some(WHITE));
+    }
+
+    #[test]
+    fn leaving_a_running_game_counts_as_a_loss() {
+        let script = vec![
+            Step::Expect("game-state"),
+            mock_server::game_state(&Position::initial()),
+            Step::Delay(Duration::from_millis(100)),
+            Step::Press(KeyCode::Esc),
+        ];
+        let game = play_out(script, "Jo", "Kim", WHITE);
+        assert_eq!(game.note, "left the game");
+        assert_eq!(game.winner(), Some(BLACK));
+    }
+
+    #[test]
+    fn moves_again_when_the_opponent_passes() {
+        let script = vec![
+            Step::Expect("game-state"),
+            mock_server::game_state(&white_passes()),
+            send("cur-turn", &""),
+            Step::Delay(Duration::from_millis(100)),
+            Step::Press(KeyCode::Char('c')),
+            Step::Press(KeyCode::Char('1')),
+            Step::Expect("move"),
+            send("cur-turn", &""),
+            Step::Delay(Duration::from_millis(100)),
+            Step::Press(KeyCode::Char('c')),
+            Step::Press(KeyCode::Char('8')),
+            Step::Expect("move"),
+            Step::Drop,
+            Step::Delay(Duration::from_millis(300)),
+            Step::Press(KeyCode::Esc),
+        ];
+        let game = play_out(script, "Hal", "Ida", BLACK);
+        assert_eq!(game.moves, "c1c8");
+        assert_eq!((game.black_discs, game.white_discs), (6, 0));
+    }
+}
@@ -3,21 +3,25 @@
 use std::io::Error;
 
 use copypasta::{ClipboardContext, ClipboardProvider};
-use crossterm::{style::Color, event::{EventStream, KeyCode, Event}};
+use crossterm::event::{KeyCode, Event};
 use futures_util::{stream::StreamExt, SinkExt};
 use reqwest::{StatusCode, Url};
 use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
 use tokio_tungstenite::tungstenite::Message;
 
 use crate::{
     custom_elements::DialogBox,
     game::macros::render_seq,
+    server,
+    servers::{lan_ip, parse_join_link, Server, Servers, LAN_PORT},
     sleep,
     termin::{
         elements::{InputWindow, Rectangle, Text},
         terminal_window::TerminalHandler,
         window::{Position, Window, WindowRef},
     },
+    theme::Theme,
 };
 
 use super::{
@@ -33,6 +37,9 @@ pub struct Online {
     pub side: char,
     pub player_id: String,
     pub game_id: String,
+    server: Server,
+    /// Where others reach the game when it is hosted here on the LAN.
+    lan_address: Option<String>,
 }
 
 impl Online {
@@ -43,6 +50,8 @@ impl Online {
             player_id: "".to_string(),
             game_id: "".to_string(),
             player_name: "".to_string(),
+            server: Server::default(),
+            lan_address: None,
         }
     }
 }
@@ -52,35 +61,27 @@ struct Lobby {
     name: String,
     white_name: String,
     black_name: String,
+    /// How the opponent joins, shown under the players.
+    status: String,
     lobby_win: WindowRef,
     game_status_win: WindowRef,
-}
-
-const HLINK: &str = "https://othellojs.onrender.com";
-const WLINK: &str = "wss://othellojs.onrender.com";
-
-macro_rules! api_link {
-    ($post:expr) => { &(HLINK.to_string() + $post) };
-    ($pre:literal, $post:expr) => {
-        if $pre == "w" {
-            WLINK.to_string() + $post
-        } else {
-            HLINK.to_string() + $post
-        }
-    }
+    theme: Theme,
 }
 
 impl Lobby {
     fn new(win: &mut WindowRef) -> Self {
         let mut lobby_win = win.new_child(Window::default().size(50, 15));
         lobby_win.set_position(Position::CenterB);
+        let theme = Theme::load();
         Self {
             name: "".to_string(),
             white_name: "".to_string(),
             black_name: "".to_string(),
+            status: "".to_string(),
             game_status_win: lobby_win
-                .new_child(Window::default().bg(Color::Green).size(40, 1).xy(9, 11)),
+                .new_child(Window::default().bg(theme.frame).size(40, 1).xy(9, 11)),
             lobby_win,
+            theme,
         }
     }
 
@@ -88,13 +89,13 @@ impl Lobby {
         terminal.clear();
         terminal.refresh().unwrap();
         self.lobby_win.clear();
-        let mut wrect = Rectangle::default().bg(Color::White).size(6, 3);
-        let mut brect = Rectangle::default().bg(Color::Black).size(6, 3);
+        let mut wrect = Rectangle::default().bg(self.theme.white).size(6, 3);
+        let mut brect = Rectangle::default().bg(self.theme.black).size(6, 3);
         let text = Text::default()
             .text("Lobby")
             .position(self.lobby_win.rect(), Position::CenterH)
             .xy_rel(0, 1);
-        self.lobby_win.set_bg(Color::Green);
+        self.lobby_win.set_bg(self.theme.frame);
 
         self.lobby_win.draw_element(&text);
 
@@ -117,7 +118,7 @@ impl Lobby {
             Position::Coord(9, 8),
         );
         self.game_status_win
-            .draw_text("copied game link to clipboard", Position::Coord(0, 0));
+            .draw_text(&self.status, Position::Coord(0, 0));
         self.game_status_win.render_to_parent();
 
         terminal.draw_window(&self.lobby_win).unwrap();
@@ -146,6 +147,11 @@ impl Online {
         self
     }
 
+    pub fn set_server(mut self, server: &Server) -> Self {
+        self.server = server.clone();
+        self
+    }
+
     fn create_game(&mut self) -> Result<(), String> {
         #[derive(Serialize)]
         struct Host<'a> {
@@ -168,7 +174,7 @@ impl Online {
         let req = reqwest::blocking::Client::new();
 
         let res = req
-            .post(api_link!("/api/create-lobby"))
+            .post(self.server.http("/api/create-lobby"))
             .json(&host)
             .send();
 
@@ -192,7 +198,7 @@ impl Online {
     }
 
     async fn connect_socket(&mut self) -> Result<WS, String> {
-        let url_str = api_link!("w", &("/api/join-game/".to_string() + &self.game_id));
+        let url_str = self.server.ws(&("/api/join-game/".to_string() + &self.game_id));
         match tokio_tungstenite::connect_async(Url::parse(&url_str).unwrap()).await {
             Ok((socket, res)) => {
                 if res.status() != StatusCode::SWITCHING_PROTOCOLS {
@@ -204,12 +210,20 @@ impl Online {
         }
     }
 
-    fn copy_link_to_clipboard(&self) {
-        let url_str = api_link!(&("/join-game/".to_string() + &self.game_id));
-        ClipboardContext::new()
-            .unwrap()
-            .set_contents(String::from(url_str).to_owned())
-            .unwrap();
+    /// Copies what the opponent needs to join: the game link, or the address
+    /// of a LAN host. Best effort, there is no clipboard on a headless machine.
+    fn share_invite(&mut self) {
+        let (invite, status) = match &self.lan_address {
+            Some(address) => (address.clone(), format!("join at {} (copied)", address)),
+            None => (
+                self.server.join_link(&self.game_id),
+                "copied game link to clipboard".to_string(),
+            ),
+        };
+        if let Ok(mut ctx) = ClipboardContext::new() {
+            _ = ctx.set_contents(invite);
+        }
+        self.lobby.status = status;
     }
 
     async fn handle_lobby_socket(
@@ -221,7 +235,7 @@ impl Online {
     ) -> NextGameState {
         match msg.event_name() {
             "game-verified" => {
-                if !msg.parse::<bool>() {
+                if !msg.parse::<bool>().unwrap_or(false) {
                     return NextGameState::Error("game not found :(".to_string());
                 } else {
                     let player_info = PlayerInfo {
@@ -251,15 +265,19 @@ impl Online {
                     playerId: Option<String>,
                     side: Option<u8>,
                 }
-                let msg: JoinPlayerInfoRes = msg.parse();
-
-                if msg.err {
-                    return NextGameState::Error(msg.msg);
-                } else {
-                    self.lobby.render(terminal);
-                    self.copy_link_to_clipboard();
-                    self.side = msg.side.unwrap() as Side;
-                    self.player_id = msg.playerId.unwrap();
+                let msg: JoinPlayerInfoRes = match msg.parse() {
+                    Ok(msg) => msg,
+                    Err(_) => return NextGameState::Continue,
+                };
+
+                match (msg.err, msg.side, msg.playerId) {
+                    (false, Some(side), Some(player_id)) => {
+                        self.share_invite();
+                        self.lobby.render(terminal);
+                        self.side = side as Side;
+                        self.player_id = player_id;
+                    }
+                    _ => return NextGameState::Error(msg.msg),
                 }
             }
             "lobby-info" => {
@@ -268,10 +286,11 @@ impl Online {
                     black: String,
                     white: String,
                 }
-                let info: LobbyInfo = msg.parse();
-                self.lobby.black_name = info.black;
-                self.lobby.white_name = info.white;
-                self.lobby.render(terminal);
+                if let Ok(info) = msg.parse::<LobbyInfo>() {
+                    self.lobby.black_name = info.black;
+                    self.lobby.white_name = info.white;
+                    self.lobby.render(terminal);
+                }
             }
             "countdown-begin" => {
                 self.lobby.game_status_win.clear();
@@ -310,7 +329,7 @@ impl Online {
         let mut dbox = DialogBox::new(35, 5)
             .position(terminal.root.rect(), Position::Coord(5, 5))
             .text("");
-        let mut event = EventStream::new();
+        let mut event = terminal.event_stream();
 
         loop {
             tokio::select!{
@@ -326,44 +345,38 @@ impl Online {
                     }
                 },
                 socket_ev = socket.next() => {
-                    match socket_ev {
-                        Some(maybe_msg) => match maybe_msg {
-                            Ok(msg) => match msg {
-                                Message::Text(msg) => {
-                                    match self.handle_lobby_socket(&mut socket, SocketMsg::from(msg), &mut dbox, terminal).await {
-                                        NextGameState::Continue => continue,
-                                        NextGameState::StartGame => {
-                                            std::mem::drop(event); 
-                                            self.lobby.game_status_win.delete();
-                                            self.lobby.lobby_win.delete();
-
-                                            let mut game = OnlineGame::new(
-                                                if self.side == 'w' {
-                                                    self.lobby.black_name.clone()
-                                                } else {
-                                                    self.lobby.white_name.clone()
-                                                },
-                                                self.side,
-                                                terminal,
-                                            );
-
-                                            game.begin_game(socket).await;
-                                            return;
-                                        }
-                                        NextGameState::Error(e) => {
-                                            dbox.error(&e);
-                                            terminal.root.draw_element(&dbox);
-                                            terminal.refresh().unwrap();
-                                            terminal.getch();
-                                            return;
-                                        }
-                                    }
-                                }
-                                _ => ()
-                            },
-                            Err(_) => ()
+                    let state = match socket_ev {
+                        Some(Ok(Message::Text(msg))) => match SocketMsg::from(msg) {
+                            Ok(msg) => self.handle_lobby_socket(&mut socket, msg, &mut dbox, terminal).await,
+                            Err(_) => NextGameState::Continue,
                         },
-                        None => ()
+                        Some(Ok(_)) => NextGameState::Continue,
+                        Some(Err(_)) | None => NextGameState::Error("connection lost :(".to_string()),
+                    };
+                    match state {
+                        NextGameState::Continue => continue,
+                        NextGameState::StartGame => {
+                            std::mem::drop(event);
+                            self.lobby.game_status_win.delete();
+                            self.lobby.lobby_win.delete();
+
+                            let mut game = OnlineGame::new(
+                                self.lobby.black_name.clone(),
+                                self.lobby.white_name.clone(),
+                                self.side,
+                                terminal,
+                            );
+
+                            game.begin_game(socket).await;
+                            return;
+                        }
+                        NextGameState::Error(e) => {
+                            dbox.error(&e);
+                            terminal.root.draw_element(&dbox);
+                            terminal.refresh().unwrap();
+                            terminal.getch();
+                            return;
+                        }
                     }
                 }
             }
@@ -392,31 +405,51 @@ impl Online {
     }
 
     pub fn join_and_start(&mut self, terminal: &mut TerminalHandler) {
-        terminal.root.clear();
-        let game_link = terminal.handle_input(|handler, root| -> String {
-            let mut input_win = root.new_child(Window::default().size(50, 10));
-            let label = Text::default().text("game link: ").xy(0, 2);
-            let mut input = InputWindow::from(
-                &mut input_win,
-                Window::default()
-                    .xy(label.x() + label.width(), label.y())
-                    .size(25, 3),
-            )
-            .start_text((0, 0))
-            .max_len(65);
-
-            input_win.set_xy_rel(2, 2);
-            input_win.draw_element(&label);
-            input_win.draw_text("Join Game", Position::CenterH);
-            input_win.render();
-
-            handler.draw_window(&root).unwrap();
-
-            let new_name = input.read_string(handler);
-            input_win.delete();
-            new_name
+        let game_link = read_input(terminal, "Join Game", "game link: ");
+        self.join_checked(terminal, |online| {
+            online.extract_game_id(game_link)?;
+            online.is_game_exist()
         });
+    }
+
+    /// Joins the game hosted on the LAN at the `ip:port` the player types.
+    pub fn join_address_and_start(&mut self, terminal: &mut TerminalHandler) {
+        let address = read_input(terminal, "Join by Address", "address: ");
+        self.join_checked(terminal, |online| {
+            online.server = Server::lan(&address)?;
+            online.find_lan_lobby()?;
+            online.is_game_exist()
+        });
+    }
+
+    /// Hosts a game on this machine for a player on the same network, who
+    /// joins it by address. The host's copy of the game is the one that
+    /// decides which moves count, and it stops when the game does.
+    pub fn host_lan_and_start(&mut self, terminal: &mut TerminalHandler) {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        match rt.block_on(TcpListener::bind(("0.0.0.0", LAN_PORT))) {
+            Ok(listener) => _ = rt.spawn(server::serve(listener, server::RECONNECT_GRACE, true)),
+            Err(e) => {
+                let mut dbox =
+                    DialogBox::new(35, 5).position(terminal.root.rect(), Position::Coord(5, 5));
+                dbox.error(&format!("unable to host on port {}: {}", LAN_PORT, e));
+                terminal.root.draw_element(&dbox);
+                terminal.refresh().unwrap();
+                terminal.getch();
+                return;
+            }
+        }
+        let ip = lan_ip().map_or("localhost".to_string(), |ip| ip.to_string());
+        self.lan_address = Some(format!("{}:{}", ip, LAN_PORT));
+        self.server = Server::lan(&format!("127.0.0.1:{}", LAN_PORT)).unwrap();
+        self.create_and_start(terminal);
+    }
 
+    /// Checks that the game picked by `find` exists and joins it.
+    fn join_checked<F>(&mut self, terminal: &mut TerminalHandler, find: F)
+    where
+        F: FnOnce(&mut Self) -> Result<bool, String>,
+    {
         let mut dbox = DialogBox::new(35, 5).position(terminal.root.rect(), Position::Coord(5, 5));
         dbox.info("checking game existence...");
         terminal.root.clear();
@@ -424,9 +457,7 @@ impl Online {
         terminal.refresh().unwrap();
         terminal.root.clear();
 
-        self.extract_game_id(game_link);
-
-        match self.is_game_exist() {
+        match find(self) {
             Ok(is_exist) => {
                 if is_exist {
                     self.connect_game(dbox, terminal);
@@ -446,20 +477,37 @@ impl Online {
         }
     }
 
-    fn extract_game_id(&mut self, link: String) {
-        match link
-            .trim_end_matches("/")
-            .split("/")
-            .collect::<Vec<&str>>()
-            .last()
-        {
-            Some(last) => {
-                self.game_id = last.to_string();
+    /// Asks a LAN host which of its lobbies is open.
+    fn find_lan_lobby(&mut self) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct Response {
+            gameId: Option<String>,
+            err: bool,
+            msg: String,
+        }
+
+        let link = self.server.http("/api/lan-lobby");
+        let res: Response = reqwest::blocking::get(link)
+            .and_then(|res| res.json())
+            .map_err(|e| e.to_string())?;
+        match (res.err, res.gameId) {
+            (false, Some(id)) => {
+                self.game_id = id;
+                Ok(())
             }
-            None => (),
+            _ => Err(res.msg),
         }
     }
 
+    /// Takes the game id from a join link, switching to the server the
+    /// link points at when it is one of the configured ones.
+    fn extract_game_id(&mut self, link: String) -> Result<(), String> {
+        let (server, id) = parse_join_link(&link, &self.server, &Servers::load().servers)?;
+        self.server = server;
+        self.game_id = id;
+        Ok(())
+    }
+
     fn is_game_exist(&mut self) -> Result<bool, String> {
         if self.game_id.is_empty() {
             return Err("invalid link.".to_string());
@@ -474,7 +522,7 @@ impl Online {
         }
 
         let req = reqwest::blocking::Client::new();
-        let link = api_link!(&("/api/game-info/".to_string() + &self.game_id));
+        let link = self.server.http(&("/api/game-info/".to_string() + &self.game_id));
 
         let res = req.get(link).send();
 
@@ -523,3 +571,144 @@ impl Online {
         }
     }
 }
+
+/// Asks for one line of text on a clear screen.
+fn read_input(terminal: &mut TerminalHandler, title: &str, label: &str) -> String {
+    terminal.root.clear();
+    terminal.handle_input(|handler, root| -> String {
+        let mut input_win = root.new_child(Window::default().size(50, 10));
+        let label = Text::default().text(label).xy(0, 2);
+        let mut input = InputWindow::from(
+            &mut input_win,
+            Window::default()
+                .xy(label.x() + label.width(), label.y())
+                .size(25, 3),
+        )
+        .start_text((0, 0))
+        .max_len(65);
+
+        input_win.set_xy_rel(2, 2);
+        input_win.draw_element(&label);
+        input_win.draw_text(title, Position::CenterH);
+        input_win.render();
+
+        handler.draw_window(&root).unwrap();
+
+        let text = input.read_string(handler);
+        input_win.delete();
+        text
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::game::{
+        board::{BLACK, WHITE},
+        history,
+        mock_server::{self, send, MockServer, Step, GAME_ID},
+        position::Position,
+    };
+    use crate::storage;
+    use crate::termin::crossterm_handler::ScriptedInput;
+
+    #[test]
+    fn creates_a_game_and_plays_it_out() {
+        let _data = storage::temp_data_dir();
+        let input = ScriptedInput::default();
+        let mut script = mock_server::lobby(BLACK, "Ann", "Bob");
+        script.extend([
+            mock_server::game_state(&Position::initial()),
+            Step::Press(KeyCode::Char('f')),
+            Step::Press(KeyCode::Char('5')),
+            Step::Expect("move"),
+            send("opponent-move", &json!({"rowIdx": 5, "colIdx": 5})),
+            Step::Send("game-over", "Bob resigned".to_string()),
+            Step::Delay(Duration::from_millis(300)),
+            Step::Press(KeyCode::Esc),
+        ]);
+        let mock = MockServer::start(script, &input);
+        let mut terminal = mock_server::terminal(&input);
+
+        let mut online = Online::new(&mut terminal.root)
+            .set_player(BLACK, "Ann")
+            .set_server(&mock.server);
+        online.create_and_start(&mut terminal);
+        assert_eq!(online.game_id, GAME_ID);
+        assert_eq!(online.player_id, "p1");
+
+        let log = mock.log();
+        assert_eq!(log[0].0, "POST /api/create-lobby");
+        assert!(log[0].1.contains(r#""hostName":"Ann""#));
+        assert!(log.contains(&("move".to_string(), r#"{"rowIdx":4,"colIdx":5}"#.to_string())));
+
+        let game = history::load()
+            .into_iter()
+            .find(|e| e.note == "Bob resigned")
+            .unwrap();
+        assert_eq!((game.black.as_str(), game.white.as_str()), ("Ann", "Bob"));
+        assert_eq!(game.moves, "f5f6");
+    }
+
+    #[test]
+    fn joins_by_link_and_gives_up_when_the_lobby_drops() {
+        let _data = storage::temp_data_dir();
+        let input = ScriptedInput::default();
+        let script = vec![
+            send("game-verified", &true),
+            Step::Expect("join-player-info"),
+            Step::Raw("not a socket message".to_string()),
+            send("join-player-info-res", &json!({"err": false, "msg": "", "playerId": "p2", "side": WHITE as u8})),
+            Step::Delay(Duration::from_millis(100)),
+            Step::Drop,
+            Step::Delay(Duration::from_millis(300)),
+            Step::Press(KeyCode::Esc),
+        ];
+        let mock = MockServer::start(script, &input);
+        let mut terminal = mock_server::terminal(&input);
+        let mut online = Online::new(&mut terminal.root)
+            .set_player(WHITE, "Ann")
+            .set_server(&mock.server);
+
+        online.extract_game_id(mock.server.join_link("nope")).unwrap();
+        assert_eq!(online.is_game_exist(), Err("game not found".to_string()));
+
+        online.extract_game_id(mock.server.join_link(GAME_ID)).unwrap();
+        assert_eq!(online.is_game_exist(), Ok(true));
+        assert_eq!(online.lobby.name, "mock");
+
+        online.connect_game(DialogBox::new(35, 5), &mut terminal);
+        assert_eq!((online.side, online.player_id.as_str()), (WHITE, "p2"));
+        assert!(mock
+            .log()
+            .iter()
+            .any(|(event, data)| event == "join-player-info" && data.contains(r#""playerName":"Ann""#)));
+    }
+
+    #[test]
+    fn finds_the_game_hosted_at_an_address() {
+        let _data = storage::temp_data_dir();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let listener = rt.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        rt.spawn(server::serve(listener, server::RECONNECT_GRACE, true));
+        let lan = Server::lan(&address).unwrap();
+        let mut terminal = mock_server::terminal(&ScriptedInput::default());
+
+        let mut guest = Online::new(&mut terminal.root).set_server(&lan);
+        assert!(guest.find_lan_lobby().is_err());
+
+        let mut host = Online::new(&mut terminal.root)
+            .set_player(BLACK, "Ann")
+            .set_server(&lan);
+        host.create_game().unwrap();
+        guest.find_lan_lobby().unwrap();
+        assert_eq!(guest.game_id, host.game_id);
+        assert_eq!(guest.is_game_exist(), Ok(true));
+        assert_eq!(guest.lobby.name, "Ann");
+    }
+}
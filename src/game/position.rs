@@ -0,0 +1,252 @@
+use std::fmt;
+
+pub const BLACK: Side = 'b';
+pub const WHITE: Side = 'w';
+pub const EMPTY: Side = '0';
+
+pub type Side = char;
+pub type Square = (usize, usize);
+
+const DIRECTIONS: [(i8, i8); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, 1),
+    (-1, -1),
+    (1, -1),
+    (1, 1),
+];
+
+/// A bare board plus the side to move, without any windows attached.
+/// Used wherever the rules are needed off screen (search, puzzles, notation).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub board: [[Side; 8]; 8],
+    pub side: Side,
+}
+
+pub fn opponent_of(side: Side) -> Side {
+    if side == WHITE {
+        BLACK
+    } else {
+        WHITE
+    }
+}
+
+pub fn square_name(row: usize, col: usize) -> String {
+    let mut s = String::new();
+    s.push((b'a' + col as u8) as char);
+    s.push((b'1' + row as u8) as char);
+    s
+}
+
+pub fn parse_square(s: &str) -> Option<Square> {
+    let mut chars = s.chars();
+    let col = chars.next()?.to_ascii_lowercase();
+    let row = chars.next()?;
+    if chars.next().is_some() || !('a'..='h').contains(&col) || !('1'..='8').contains(&row) {
+        return None;
+    }
+    Some((row as usize - '1' as usize, col as usize - 'a' as usize))
+}
+
+impl Position {
+    pub fn empty(side: Side) -> Self {
+        Self {
+            board: [[EMPTY; 8]; 8],
+            side,
+        }
+    }
+
+    /// The standard start position, black to move.
+    pub fn initial() -> Self {
+        let mut pos = Self::empty(BLACK);
+        pos.board[3][3] = WHITE;
+        pos.board[3][4] = BLACK;
+        pos.board[4][3] = BLACK;
+        pos.board[4][4] = WHITE;
+        pos
+    }
+
+    /// 64 squares of `X` (black), `O` (white) or `-` followed by the side to move.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let chars: Vec<char> = s.chars().filter(|c| !c.is_whitespace()).collect();
+        if chars.len() != 65 {
+            return Err("expected 64 squares and the side to move".to_string());
+        }
+
+        let mut pos = Self::empty(BLACK);
+        for (idx, ch) in chars[..64].iter().enumerate() {
+            pos.board[idx / 8][idx % 8] = match ch {
+                'X' | 'x' | '*' => BLACK,
+                'O' | 'o' => WHITE,
+                '-' | '.' => EMPTY,
+                _ => return Err(format!("invalid square '{}'", ch)),
+            };
+        }
+        pos.side = match chars[64] {
+            'X' | 'x' | '*' => BLACK,
+            'O' | 'o' => WHITE,
+            ch => return Err(format!("invalid side to move '{}'", ch)),
+        };
+        Ok(pos)
+    }
+
+    pub fn count(&self, side: Side) -> u8 {
+        self.board
+            .iter()
+            .map(|row| row.iter().filter(|s| **s == side).count() as u8)
+            .sum()
+    }
+
+    pub fn empties(&self) -> u8 {
+        self.count(EMPTY)
+    }
+
+    fn flips_in_dir(&self, row: usize, col: usize, dr: i8, dc: i8, side: Side) -> usize {
+        let opponent = opponent_of(side);
+        let (mut r, mut c) = (row as i8 + dr, col as i8 + dc);
+        let mut count = 0;
+
+        while (0..8).contains(&r) && (0..8).contains(&c) {
+            let cell = self.board[r as usize][c as usize];
+            if cell == side {
+                return count;
+            } else if cell != opponent {
+                break;
+            }
+            count += 1;
+            r += dr;
+            c += dc;
+        }
+        0
+    }
+
+    pub fn flips_for(&self, row: usize, col: usize, side: Side) -> Vec<Square> {
+        let mut flips = vec![];
+        if self.board[row][col] != EMPTY {
+            return flips;
+        }
+        for (dr, dc) in DIRECTIONS {
+            for step in 1..=self.flips_in_dir(row, col, dr, dc, side) as i8 {
+                flips.push((
+                    (row as i8 + dr * step) as usize,
+                    (col as i8 + dc * step) as usize,
+                ));
+            }
+        }
+        flips
+    }
+
+    pub fn is_legal_for(&self, row: usize, col: usize, side: Side) -> bool {
+        self.board[row][col] == EMPTY
+            && DIRECTIONS
+                .iter()
+                .any(|(dr, dc)| self.flips_in_dir(row, col, *dr, *dc, side) > 0)
+    }
+
+    pub fn flips(&self, row: usize, col: usize) -> Vec<Square> {
+        self.flips_for(row, col, self.side)
+    }
+
+    pub fn is_legal(&self, row: usize, col: usize) -> bool {
+        self.is_legal_for(row, col, self.side)
+    }
+
+    /// Why the side to move can't play `(row, col)`, or `None` if it can.
+    pub fn why_illegal(&self, row: usize, col: usize) -> Option<String> {
+        let name = square_name(row, col);
+        if self.board[row][col] != EMPTY {
+            return Some(format!("{} is already taken", name));
+        }
+        if !self.flips(row, col).is_empty() {
+            return None;
+        }
+        let opponent = opponent_of(self.side);
+        let touches = DIRECTIONS.iter().any(|(dr, dc)| {
+            let (r, c) = (row as i8 + dr, col as i8 + dc);
+            (0..8).contains(&r)
+                && (0..8).contains(&c)
+                && self.board[r as usize][c as usize] == opponent
+        });
+        Some(if touches {
+            format!(
+                "{} flips nothing, no line from it ends on your own disc",
+                name
+            )
+        } else {
+            format!("{} does not touch any opponent disc", name)
+        })
+    }
+
+    pub fn legal_moves_for(&self, side: Side) -> Vec<Square> {
+        let mut moves = vec![];
+        for row in 0..8 {
+            for col in 0..8 {
+                if self.is_legal_for(row, col, side) {
+                    moves.push((row, col));
+                }
+            }
+        }
+        moves
+    }
+
+    pub fn legal_moves(&self) -> Vec<Square> {
+        self.legal_moves_for(self.side)
+    }
+
+    pub fn has_moves(&self) -> bool {
+        (0..64).any(|idx| self.is_legal(idx / 8, idx % 8))
+    }
+
+    /// Plays a move for the side to move and hands the turn over.
+    /// Returns the flipped squares, empty (and nothing changes) if the move is illegal.
+    pub fn play(&mut self, row: usize, col: usize) -> Vec<Square> {
+        let flips = self.flips(row, col);
+        if flips.is_empty() {
+            return flips;
+        }
+        for (r, c) in &flips {
+            self.board[*r][*c] = self.side;
+        }
+        self.board[row][col] = self.side;
+        self.side = opponent_of(self.side);
+        flips
+    }
+
+    pub fn pass(&mut self) {
+        self.side = opponent_of(self.side);
+    }
+}
+
+/// Writes the notation `parse` reads: `X`, `O` and `-` for the squares, then the side to move.
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = |side: Side| match side {
+            BLACK => 'X',
+            WHITE => 'O',
+            _ => '-',
+        };
+        for row in self.board {
+            for cell in row {
+                write!(f, "{}", symbol(cell))?;
+            }
+        }
+        write!(f, " {}", symbol(self.side))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn illegal_moves_are_explained() {
+        let pos = Position::initial();
+        assert_eq!(pos.why_illegal(2, 3), None);
+        assert_eq!(pos.why_illegal(3, 3).unwrap(), "d4 is already taken");
+        assert!(pos.why_illegal(2, 2).unwrap().contains("flips nothing"));
+        assert!(pos.why_illegal(0, 0).unwrap().contains("does not touch"));
+    }
+}
@@ -0,0 +1,305 @@
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    menu::Return,
+    storage,
+    termin::{
+        terminal_window::TerminalHandler,
+        window::{Position as WinPosition, Window, WindowRef},
+    },
+};
+
+use super::{
+    board::{Side, WHITE},
+    position::{opponent_of, parse_square, square_name, Position, Square},
+    search::{self, EXACT},
+    CursorResult, Game,
+};
+
+const PUZZLES: &str = include_str!("../../assets/puzzles.txt");
+const STATS_FILE: &str = "puzzles.json";
+/// Columns kept right of the board for the puzzle text.
+const INFO_WIDTH: u32 = 40;
+
+#[derive(Debug)]
+pub struct Puzzle {
+    pub position: Position,
+    pub answer: Square,
+    pub title: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct PuzzleStats {
+    pub solved: u32,
+    pub failed: u32,
+    pub next: usize,
+}
+
+enum Feedback {
+    Next,
+    Retry,
+    Quit,
+}
+
+impl Puzzle {
+    fn parse(line: &str) -> Result<Self, String> {
+        let mut parts = line.split_whitespace();
+        let squares = parts.next().ok_or("missing squares")?;
+        let side = parts.next().ok_or("missing side to move")?;
+        let position = Position::parse(&(squares.to_string() + side))?;
+        let answer = parts
+            .next()
+            .and_then(parse_square)
+            .ok_or("missing or invalid answer")?;
+        let title = parts.collect::<Vec<&str>>().join(" ");
+
+        if !position.is_legal(answer.0, answer.1) {
+            return Err(format!(
+                "answer {} is not a legal move",
+                square_name(answer.0, answer.1)
+            ));
+        }
+
+        Ok(Self {
+            position,
+            answer,
+            title,
+        })
+    }
+}
+
+pub fn load_puzzles() -> Vec<Puzzle> {
+    PUZZLES
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| Puzzle::parse(line).ok())
+        .collect()
+}
+
+fn side_name(side: Side) -> &'static str {
+    if side == WHITE {
+        "White"
+    } else {
+        "Black"
+    }
+}
+
+fn describe(score: i32) -> String {
+    let margin = score / EXACT;
+    if margin > 0 {
+        format!("wins by {}", margin)
+    } else if margin < 0 {
+        format!("loses by {}", -margin)
+    } else {
+        "draws".to_string()
+    }
+}
+
+fn draw_lines(win: &mut WindowRef, top: u32, lines: &[String]) {
+    for (idx, line) in lines.iter().enumerate() {
+        win.draw_text(line, WinPosition::Coord(0, top + idx as u32));
+    }
+}
+
+struct PuzzleScreen {
+    win: WindowRef,
+    info_win: WindowRef,
+    game: Game,
+    stats: PuzzleStats,
+}
+
+impl PuzzleScreen {
+    fn new(terminal: &mut TerminalHandler) -> Self {
+        let mut win = terminal
+            .root
+            .new_child(Window::default().size(terminal.root.width(), terminal.root.height()));
+        let mut game = Game::with_panel(&mut win, INFO_WIDTH);
+        let info_win = win.new_child(Window::default().size(INFO_WIDTH, 14).xy(game.panel_x(), 4));
+        game.add_panel(&info_win);
+
+        Self {
+            win,
+            info_win,
+            game,
+            stats: storage::load_json(STATS_FILE),
+        }
+    }
+
+    fn render_info(&mut self, idx: usize, total: usize, puzzle: &Puzzle, feedback: &[String]) {
+        self.info_win.clear();
+        draw_lines(
+            &mut self.info_win,
+            0,
+            &[
+                format!("Puzzle {}/{}", idx + 1, total),
+                format!(
+                    "{} to move: {}",
+                    side_name(puzzle.position.side),
+                    puzzle.title
+                ),
+                "".to_string(),
+                format!(
+                    "solved: {}  failed: {}",
+                    self.stats.solved, self.stats.failed
+                ),
+            ],
+        );
+        draw_lines(&mut self.info_win, 5, feedback);
+        self.info_win.render();
+    }
+
+    fn check_move(&mut self, puzzle: &Puzzle, played: Square) -> Vec<String> {
+        let pos = puzzle.position;
+        let scores = search::score_moves(&pos, search::default_depth(&pos));
+        let score_of = |sq: Square| scores.iter().find(|(m, _)| *m == sq).map_or(0, |(_, s)| *s);
+        let (row, col) = played;
+
+        if played == puzzle.answer {
+            self.stats.solved += 1;
+            return vec![
+                format!(
+                    "Correct! {} {}.",
+                    square_name(row, col),
+                    describe(score_of(played))
+                ),
+                "".to_string(),
+                "enter: next puzzle  esc: back".to_string(),
+            ];
+        }
+
+        self.stats.failed += 1;
+        let mut lines = vec![format!(
+            "Wrong: {} {}.",
+            square_name(row, col),
+            describe(score_of(played))
+        )];
+
+        let mut after = pos;
+        after.play(row, col);
+        if !after.has_moves() {
+            after.pass();
+        }
+        if let Some(((r, c), _)) = search::best_move(&after, search::default_depth(&after)) {
+            lines.push(format!(
+                "Refutation: {} plays {}.",
+                side_name(after.side),
+                square_name(r, c)
+            ));
+            if after.side != pos.side {
+                self.game.set_cur_turn_side(opponent_of(pos.side));
+            }
+            self.game.board.move_cursor(c as u16, r as u16);
+            self.game.play_move();
+            self.game.render_cursor = true;
+        }
+        lines.push("".to_string());
+        lines.push("r: retry  enter: next  esc: back".to_string());
+        lines
+    }
+
+    fn wait_for_next(&mut self, terminal: &mut TerminalHandler) -> Feedback {
+        loop {
+            match terminal.getch() {
+                KeyCode::Enter | KeyCode::Right => return Feedback::Next,
+                KeyCode::Char('r') => return Feedback::Retry,
+                KeyCode::Esc => return Feedback::Quit,
+                _ => (),
+            }
+        }
+    }
+
+    fn run(&mut self, terminal: &mut TerminalHandler) {
+        let puzzles = load_puzzles();
+        if puzzles.is_empty() {
+            return;
+        }
+        let mut idx = self.stats.next % puzzles.len();
+
+        loop {
+            let puzzle = &puzzles[idx];
+            terminal.clear();
+            self.game.load_position(&puzzle.position);
+            self.game.render_cur_turn_side();
+            self.render_info(
+                idx,
+                puzzles.len(),
+                puzzle,
+                &["arrows: move  enter: play  esc: back".to_string()],
+            );
+
+            self.game.board.calc_available_moves(puzzle.position.side);
+            self.game.board.place_cursor_on_legal_position();
+            if self.game.enable_cursor_movement(terminal) == CursorResult::Cancelled {
+                break;
+            }
+
+            let (col, row) = self.game.board.cursor_xy();
+            let played = (row as usize, col as usize);
+            let feedback = self.check_move(puzzle, played);
+            self.game.play_animation(terminal);
+            self.game.render_board();
+            self.game.render_cursor = false;
+            self.render_info(idx, puzzles.len(), puzzle, &feedback);
+            terminal.refresh().unwrap();
+
+            let next = (idx + 1) % puzzles.len();
+            match self.wait_for_next(terminal) {
+                Feedback::Next => idx = next,
+                Feedback::Retry => (),
+                Feedback::Quit => {
+                    self.stats.next = if played == puzzle.answer { next } else { idx };
+                    break;
+                }
+            }
+            self.stats.next = idx;
+            _ = storage::save_json(STATS_FILE, &self.stats);
+        }
+
+        _ = storage::save_json(STATS_FILE, &self.stats);
+        self.win.delete();
+    }
+}
+
+pub fn play_puzzles<Ctx>(terminal: &mut TerminalHandler, _: &mut Ctx) -> Return {
+    PuzzleScreen::new(terminal).run(terminal);
+    Return::None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_puzzles_load_with_a_legal_answer() {
+        let puzzles = load_puzzles();
+        assert_eq!(
+            puzzles.len(),
+            PUZZLES
+                .lines()
+                .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+                .count()
+        );
+        for puzzle in puzzles {
+            let (row, col) = puzzle.answer;
+            assert!(puzzle.position.is_legal(row, col), "{:?}", puzzle.title);
+        }
+    }
+
+    #[test]
+    #[ignore = "solves every puzzle to the end, run with --ignored"]
+    fn bundled_puzzles_have_a_unique_best_move() {
+        for puzzle in load_puzzles() {
+            let pos = puzzle.position;
+            let scores = search::score_moves(&pos, pos.empties());
+            let (best, score) = scores[0];
+            assert_eq!(best, puzzle.answer, "{:?}", puzzle.title);
+            assert!(
+                scores.len() < 2 || scores[1].1 < score,
+                "{:?}",
+                puzzle.title
+            );
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::storage;
+
+use super::{
+    board::Side,
+    ggf::GgfGame,
+    position::{parse_square, square_name, Position, Square},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ply {
+    Move(Square),
+    Pass,
+}
+
+/// Start position plus every ply played from it, passes included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub start: Position,
+    pub plies: Vec<Ply>,
+    pub black: String,
+    pub white: String,
+    current: Position,
+}
+
+impl GameRecord {
+    pub fn new(start: Position) -> Self {
+        Self {
+            start,
+            plies: vec![],
+            black: String::new(),
+            white: String::new(),
+            current: start,
+        }
+    }
+
+    pub fn current(&self) -> Position {
+        self.current
+    }
+
+    /// The position after the first `ply` plies.
+    pub fn position_at(&self, ply: usize) -> Position {
+        let mut pos = self.start;
+        for p in self.plies.iter().take(ply) {
+            match p {
+                Ply::Move((row, col)) => {
+                    pos.play(*row, *col);
+                }
+                Ply::Pass => pos.pass(),
+            }
+        }
+        pos
+    }
+
+    /// The squares played in the first `ply` plies, passes left out.
+    pub fn moves_until(&self, ply: usize) -> Vec<Square> {
+        self.plies
+            .iter()
+            .take(ply)
+            .filter_map(|p| match p {
+                Ply::Move(square) => Some(*square),
+                Ply::Pass => None,
+            })
+            .collect()
+    }
+
+    pub fn pass(&mut self) {
+        self.plies.push(Ply::Pass);
+        self.current.pass();
+    }
+
+    /// Records a move by `side`, inserting the pass that must have happened
+    /// if it is not that side's turn. Illegal moves are ignored.
+    pub fn play(&mut self, side: Side, (row, col): Square) -> bool {
+        if self.current.side != side {
+            self.pass();
+        }
+        if !self.current.is_legal(row, col) {
+            return false;
+        }
+        self.current.play(row, col);
+        self.plies.push(Ply::Move((row, col)));
+        true
+    }
+
+    /// The compact transcript (`f5d6c3...`). Passes are implied, as usual.
+    pub fn to_transcript(&self) -> String {
+        self.moves_until(self.plies.len())
+            .iter()
+            .map(|(row, col)| square_name(*row, *col))
+            .collect()
+    }
+
+    /// Parses a transcript played from `start`. Separators, move numbers and
+    /// explicit passes (`--`, `pa`, `ps`) are accepted; missing passes are inferred.
+    pub fn from_transcript_at(start: Position, transcript: &str) -> Result<Self, String> {
+        let mut record = Self::new(start);
+        let chars: Vec<char> = transcript
+            .chars()
+            .filter(|c| c.is_ascii_alphabetic() || *c == '-' || c.is_ascii_digit())
+            .collect();
+        let mut idx = 0;
+
+        while idx < chars.len() {
+            if chars[idx].is_ascii_digit() && (idx == 0 || !chars[idx - 1].is_ascii_alphabetic()) {
+                idx += 1;
+                continue;
+            }
+            if idx + 1 >= chars.len() {
+                return Err(format!("incomplete move at '{}'", chars[idx]));
+            }
+            let token: String = chars[idx..idx + 2]
+                .iter()
+                .collect::<String>()
+                .to_lowercase();
+            idx += 2;
+
+            if token == "--" || token == "pa" || token == "ps" {
+                if record.current.has_moves() {
+                    return Err(format!(
+                        "pass at move {} while moves are available",
+                        record.plies.len() + 1
+                    ));
+                }
+                record.pass();
+                continue;
+            }
+
+            let (row, col) = parse_square(&token).ok_or(format!("invalid move '{}'", token))?;
+            if !record.current.has_moves() {
+                record.pass();
+            }
+            if !record.play(record.current.side, (row, col)) {
+                return Err(format!(
+                    "illegal move {} at move {}",
+                    token,
+                    record.plies.len() + 1
+                ));
+            }
+        }
+
+        Ok(record)
+    }
+
+    pub fn from_transcript(transcript: &str) -> Result<Self, String> {
+        Self::from_transcript_at(Position::initial(), transcript)
+    }
+
+    /// Parses a GGF game, a position string or a plain transcript.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Ok(pos) = Position::parse(text) {
+            return Ok(Self::new(pos));
+        }
+        if text.trim_start().starts_with("(;") {
+            return GgfGame::parse_all(text)?
+                .into_iter()
+                .next()
+                .map(|game| game.record)
+                .ok_or("no game found".to_string());
+        }
+        Self::from_transcript(text)
+    }
+
+    /// Loads a record from a file path, or parses the text itself.
+    pub fn load(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let path = Path::new(input);
+        if path.is_file() {
+            let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+            return Self::parse(&content);
+        }
+        Self::parse(input)
+    }
+
+    /// Saves the game as GGF, which unlike a transcript keeps the start
+    /// position and the players.
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let dir = storage::data_path("games")?;
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let path = dir.join(format!("game-{}.ggf", storage::timestamp()));
+        let ggf = GgfGame::from_record(self.clone()).to_ggf();
+        fs::write(&path, ggf + "\n").map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::{BLACK, WHITE};
+
+    #[test]
+    fn transcript_round_trip() {
+        let record = GameRecord::from_transcript("f5d6c3d3c4").unwrap();
+        assert_eq!(record.plies.len(), 5);
+        assert_eq!(record.to_transcript(), "f5d6c3d3c4");
+        assert_eq!(record.current().side, WHITE);
+        assert_eq!(record.current().count(BLACK), 6);
+    }
+
+    #[test]
+    fn transcript_accepts_separators_and_rejects_illegal_moves() {
+        let record = GameRecord::from_transcript("1. F5 d6 2. C3, d3").unwrap();
+        assert_eq!(record.to_transcript(), "f5d6c3d3");
+        assert!(GameRecord::from_transcript("f5f5").is_err());
+        assert!(GameRecord::from_transcript("a1").is_err());
+    }
+
+    #[test]
+    fn position_strings_round_trip() {
+        let pos = GameRecord::from_transcript("f5d6c3").unwrap().current();
+        let notation = pos.to_string();
+        assert_eq!(notation.len(), 66);
+        assert!(notation.ends_with(" O"));
+        assert_eq!(GameRecord::parse(&notation).unwrap().start, pos);
+    }
+
+    #[test]
+    fn passes_are_inferred() {
+        let mut start = Position::parse(&("XO".to_string() + &"-".repeat(62) + "O")).unwrap();
+        start.board[0][3] = WHITE;
+        let record = GameRecord::from_transcript_at(start, "c1").unwrap();
+        assert_eq!(record.plies, vec![Ply::Pass, Ply::Move((0, 2))]);
+    }
+}
@@ -0,0 +1,167 @@
+use crossterm::event::KeyCode;
+
+use crate::{
+    custom_elements::{prompt, show_dialog, DialogBox},
+    menu::Return,
+    termin::{
+        terminal_window::TerminalHandler,
+        window::{Position, Window, WindowRef},
+    },
+};
+
+use super::{
+    board::{SvgOptions, WHITE},
+    move_list::{self, MoveList},
+    position::square_name,
+    record::{GameRecord, Ply},
+    Game,
+};
+
+struct Replay {
+    win: WindowRef,
+    game: Game,
+    moves: MoveList,
+    record: GameRecord,
+    ply: usize,
+    numbers: bool,
+}
+
+impl Replay {
+    fn new(terminal: &mut TerminalHandler, record: GameRecord) -> Self {
+        let mut win = terminal
+            .root
+            .new_child(Window::default().size(terminal.root.width(), terminal.root.height()));
+        let game = Game::with_panel(&mut win, move_list::WIDTH);
+        let height = win.height().saturating_sub(7);
+        let moves = MoveList::new(&mut win, game.panel_x(), 4, height);
+
+        Self {
+            win,
+            game,
+            moves,
+            record,
+            ply: 0,
+            numbers: true,
+        }
+    }
+
+    fn render(&mut self, terminal: &mut TerminalHandler) {
+        terminal.clear();
+        self.win.clear();
+        self.win.draw_text(
+            "left/right: step  home/end: jump  x: diagram  n: numbers  esc: back",
+            Position::Coord(1, 3),
+        );
+        self.win.render();
+
+        self.game.show_position(&self.record.position_at(self.ply));
+        self.game.render_cur_turn_side();
+        self.game.render_board();
+
+        let status = match self.ply.checked_sub(1).map(|idx| self.record.plies[idx]) {
+            Some(Ply::Move((row, col))) => {
+                let before = self.record.position_at(self.ply - 1);
+                let flips = before.flips(row, col);
+                let theme = self.game.theme();
+                self.game.mark_squares(&flips, "<>", theme.mark);
+                self.game.mark_squares(&[(row, col)], "[]", theme.highlight);
+                format!(
+                    "ply {}/{}: {} {} flips {}",
+                    self.ply,
+                    self.record.plies.len(),
+                    if before.side == WHITE {
+                        "white"
+                    } else {
+                        "black"
+                    },
+                    square_name(row, col),
+                    flips.len()
+                )
+            }
+            Some(Ply::Pass) => format!("ply {}/{}: pass", self.ply, self.record.plies.len()),
+            None => format!(
+                "{} vs {}, start position",
+                self.record.black, self.record.white
+            ),
+        };
+        self.game.set_status(&status);
+        self.moves.render(&self.record, self.ply);
+        terminal.refresh().unwrap();
+    }
+
+    fn save_diagram(&mut self) {
+        let moves = self.record.moves_until(self.ply);
+        let opts = SvgOptions {
+            coordinates: true,
+            legal_moves: vec![],
+            last_move: moves.last().copied(),
+            numbers: if self.numbers {
+                moves
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, sq)| (*sq, idx + 1))
+                    .collect()
+            } else {
+                vec![]
+            },
+        };
+        self.game.save_diagram(&opts);
+    }
+
+    fn run(&mut self, terminal: &mut TerminalHandler) {
+        let mut redraw = true;
+        loop {
+            if redraw {
+                self.render(terminal);
+            }
+            redraw = true;
+            match terminal.getch() {
+                KeyCode::Left => self.ply = self.ply.saturating_sub(1),
+                KeyCode::Right => self.ply = (self.ply + 1).min(self.record.plies.len()),
+                KeyCode::Home | KeyCode::Up => self.ply = 0,
+                KeyCode::End | KeyCode::Down => self.ply = self.record.plies.len(),
+                KeyCode::Char('n') => {
+                    self.numbers = !self.numbers;
+                    self.game.set_status(if self.numbers {
+                        "diagrams will number the moves"
+                    } else {
+                        "diagrams without move numbers"
+                    });
+                    terminal.refresh().unwrap();
+                    redraw = false;
+                }
+                KeyCode::Char('x') | KeyCode::Char('d') => {
+                    self.save_diagram();
+                    terminal.refresh().unwrap();
+                    redraw = false;
+                }
+                KeyCode::Esc => break,
+                _ => (),
+            }
+        }
+        self.win.delete();
+    }
+}
+
+/// Steps through `record` one ply at a time.
+pub fn replay(terminal: &mut TerminalHandler, record: GameRecord) {
+    Replay::new(terminal, record).run(terminal);
+}
+
+pub fn replay_game<Ctx>(terminal: &mut TerminalHandler, _: &mut Ctx) -> Return {
+    let input = prompt(terminal, "Replay Game", "transcript or file: ", 36, 144);
+    if input.trim().is_empty() {
+        return Return::None;
+    }
+
+    match GameRecord::load(&input) {
+        Ok(record) => replay(terminal, record),
+        Err(e) => {
+            let mut dbox =
+                DialogBox::new(35, 5).position(terminal.root.rect(), Position::Coord(5, 5));
+            dbox.error(&e);
+            show_dialog(terminal, &dbox);
+        }
+    }
+    Return::None
+}
@@ -0,0 +1,123 @@
+use super::position::{opponent_of, Position, Square};
+
+/// Scale applied to the final disc margin so that a decided game always
+/// outweighs any heuristic evaluation.
+pub const EXACT: i32 = 1000;
+
+/// Positions with at most this many empty squares are searched to the end.
+pub const EXACT_EMPTIES: u8 = 12;
+
+const WEIGHTS: [[i32; 8]; 8] = [
+    [100, -20, 10, 5, 5, 10, -20, 100],
+    [-20, -50, -2, -2, -2, -2, -50, -20],
+    [10, -2, -1, -1, -1, -1, -2, 10],
+    [5, -2, -1, -1, -1, -1, -2, 5],
+    [5, -2, -1, -1, -1, -1, -2, 5],
+    [10, -2, -1, -1, -1, -1, -2, 10],
+    [-20, -50, -2, -2, -2, -2, -50, -20],
+    [100, -20, 10, 5, 5, 10, -20, 100],
+];
+
+fn final_score(pos: &Position) -> i32 {
+    (pos.count(pos.side) as i32 - pos.count(opponent_of(pos.side)) as i32) * EXACT
+}
+
+/// Heuristic score of `pos` from the point of view of the side to move.
+pub fn evaluate(pos: &Position) -> i32 {
+    let opponent = opponent_of(pos.side);
+    let mut score = 0;
+
+    for (cells, weights) in pos.board.iter().zip(WEIGHTS) {
+        for (cell, weight) in cells.iter().zip(weights) {
+            if *cell == pos.side {
+                score += weight;
+            } else if *cell == opponent {
+                score -= weight;
+            }
+        }
+    }
+
+    let mobility = pos.legal_moves().len() as i32 - pos.legal_moves_for(opponent).len() as i32;
+    score + mobility * 5
+}
+
+fn ordered_moves(pos: &Position) -> Vec<Square> {
+    let mut moves = pos.legal_moves();
+    moves.sort_by_key(|(r, c)| -WEIGHTS[*r][*c]);
+    moves
+}
+
+fn negamax(pos: &Position, depth: u8, mut alpha: i32, beta: i32) -> i32 {
+    let moves = ordered_moves(pos);
+
+    if moves.is_empty() {
+        let mut passed = *pos;
+        passed.pass();
+        if !passed.has_moves() {
+            return final_score(pos);
+        }
+        return -negamax(&passed, depth, -beta, -alpha);
+    }
+
+    if depth == 0 {
+        return evaluate(pos);
+    }
+
+    let mut best = i32::MIN + 1;
+    for (row, col) in moves {
+        let mut next = *pos;
+        next.play(row, col);
+        let score = -negamax(&next, depth - 1, -beta, -alpha);
+        if score > best {
+            best = score;
+        }
+        if best > alpha {
+            alpha = best;
+        }
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+/// Depth used by default: the whole game once the end is near enough.
+pub fn default_depth(pos: &Position) -> u8 {
+    if pos.empties() <= EXACT_EMPTIES {
+        pos.empties()
+    } else {
+        4
+    }
+}
+
+/// Every legal move with its score for the side to move, best first.
+pub fn score_moves(pos: &Position, depth: u8) -> Vec<(Square, i32)> {
+    let mut scores: Vec<(Square, i32)> = ordered_moves(pos)
+        .into_iter()
+        .map(|(row, col)| {
+            let mut next = *pos;
+            next.play(row, col);
+            let score = -negamax(&next, depth.saturating_sub(1), i32::MIN + 1, i32::MAX);
+            ((row, col), score)
+        })
+        .collect();
+    scores.sort_by_key(|(_, score)| -score);
+    scores
+}
+
+pub fn best_move(pos: &Position, depth: u8) -> Option<(Square, i32)> {
+    let moves = ordered_moves(pos);
+    let mut best: Option<(Square, i32)> = None;
+    let mut alpha = i32::MIN + 1;
+
+    for (row, col) in moves {
+        let mut next = *pos;
+        next.play(row, col);
+        let score = -negamax(&next, depth.saturating_sub(1), i32::MIN + 1, -alpha);
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some(((row, col), score));
+        }
+    }
+    best
+}
@@ -9,8 +9,8 @@ pub struct SocketMsg {
 }
 
 impl SocketMsg {
-    pub fn from(s: String) -> SocketMsg {
-        serde_json::from_str(&s).unwrap()
+    pub fn from(s: String) -> Result<SocketMsg, serde_json::Error> {
+        serde_json::from_str(&s)
     }
 
     pub fn to_string<T: Serialize>(ev_name: &str, data: &T) -> Result<String, serde_json::Error> {
@@ -20,8 +20,8 @@ impl SocketMsg {
         })
     }
 
-    pub fn parse<'a, T: Deserialize<'a>>(&'a self) -> T {
-        serde_json::from_str(&self.data).unwrap()
+    pub fn parse<'a, T: Deserialize<'a>>(&'a self) -> Result<T, serde_json::Error> {
+        serde_json::from_str(&self.data)
     }
 
     pub fn event_name(&self) -> &str {
@@ -0,0 +1,232 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    custom_elements::{prompt, ListScreen},
+    menu::Return,
+    storage,
+    termin::terminal_window::TerminalHandler,
+};
+
+use super::{
+    board::{BLACK, WHITE},
+    history::{self, Ending, HistoryEntry},
+};
+
+const STATS_FILE: &str = "stats.json";
+
+/// Games before `since` are left out, which is how the stats are reset
+/// without throwing the history away.
+#[derive(Serialize, Deserialize, Default)]
+struct StatsSettings {
+    since: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Tally {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub margin: i32,
+}
+
+impl Tally {
+    fn add(&mut self, outcome: Ordering, margin: i32) {
+        match outcome {
+            Ordering::Greater => self.wins += 1,
+            Ordering::Equal => self.draws += 1,
+            Ordering::Less => self.losses += 1,
+        }
+        self.margin += margin;
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn avg_margin(&self) -> f32 {
+        if self.games() == 0 {
+            0.0
+        } else {
+            self.margin as f32 / self.games() as f32
+        }
+    }
+
+    fn line(&self, label: &str) -> String {
+        format!(
+            "{:<20} {:>4} games  {:>3}W {:>3}D {:>3}L  {:>5.1}% won  avg margin {:+.1}",
+            label,
+            self.games(),
+            self.wins,
+            self.draws,
+            self.losses,
+            if self.games() == 0 {
+                0.0
+            } else {
+                self.wins as f32 * 100.0 / self.games() as f32
+            },
+            self.avg_margin()
+        )
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Stats {
+    pub overall: Tally,
+    pub as_black: Tally,
+    pub as_white: Tally,
+    pub by_opponent: BTreeMap<String, Tally>,
+    pub longest_streak: u32,
+    pub current_streak: u32,
+}
+
+/// Tallies the games in `entries` (oldest first) that had a local player.
+pub fn compute(entries: &[HistoryEntry]) -> Stats {
+    let mut stats = Stats::default();
+
+    for entry in entries {
+        let (margin, opponent, tally) = match entry.my_side {
+            Some(BLACK) => (
+                entry.black_discs as i32 - entry.white_discs as i32,
+                &entry.white,
+                &mut stats.as_black,
+            ),
+            Some(WHITE) => (
+                entry.white_discs as i32 - entry.black_discs as i32,
+                &entry.black,
+                &mut stats.as_white,
+            ),
+            _ => continue,
+        };
+        if entry.ending == Some(Ending::Unfinished) {
+            continue;
+        }
+        let outcome = match entry.winner() {
+            Some(winner) if Some(winner) == entry.my_side => Ordering::Greater,
+            Some(_) => Ordering::Less,
+            None => Ordering::Equal,
+        };
+        tally.add(outcome, margin);
+        stats.overall.add(outcome, margin);
+        stats
+            .by_opponent
+            .entry(opponent.clone())
+            .or_default()
+            .add(outcome, margin);
+
+        stats.current_streak = if outcome == Ordering::Greater {
+            stats.current_streak + 1
+        } else {
+            0
+        };
+        stats.longest_streak = stats.longest_streak.max(stats.current_streak);
+    }
+    stats
+}
+
+fn lines(stats: &Stats) -> Vec<String> {
+    let mut lines = vec![
+        stats.overall.line("overall"),
+        stats.as_black.line("as black"),
+        stats.as_white.line("as white"),
+        format!(
+            "win streak: {} current, {} longest",
+            stats.current_streak, stats.longest_streak
+        ),
+        "".to_string(),
+        "by opponent:".to_string(),
+    ];
+    lines.extend(
+        stats
+            .by_opponent
+            .iter()
+            .map(|(name, tally)| tally.line(name)),
+    );
+    lines
+}
+
+pub fn stats_screen<Ctx>(terminal: &mut TerminalHandler, _: &mut Ctx) -> Return {
+    let mut settings: StatsSettings = storage::load_json(STATS_FILE);
+    let mut screen = ListScreen::new(terminal);
+
+    loop {
+        let entries: Vec<HistoryEntry> = history::load()
+            .into_iter()
+            .filter(|e| e.date >= settings.since)
+            .collect();
+        screen.list.set_items(lines(&compute(&entries)));
+        let heading = if settings.since == 0 {
+            "Stats over all games".to_string()
+        } else {
+            let (year, month, day, ..) = storage::civil_time(settings.since);
+            format!("Stats since {}-{:02}-{:02}", year, month, day)
+        };
+
+        screen.render(terminal, &heading, "up/down: scroll  r: reset  esc: back");
+        match terminal.getch() {
+            KeyCode::Esc => break,
+            KeyCode::Char('r') => {
+                let answer = prompt(terminal, "Reset Stats", "reset stats? (y/n): ", 4, 3);
+                if answer.trim().to_lowercase().starts_with('y') {
+                    settings.since = storage::timestamp();
+                    _ = storage::save_json(STATS_FILE, &settings);
+                }
+            }
+            k => screen.navigate(k),
+        }
+    }
+    screen.win.delete();
+    Return::None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::history::Mode;
+
+    fn entry(my_side: Option<char>, opponent: &str, black: u8, white: u8) -> HistoryEntry {
+        let (b, w) = if my_side == Some(WHITE) {
+            (opponent, "me")
+        } else {
+            ("me", opponent)
+        };
+        HistoryEntry {
+            date: 0,
+            mode: Mode::Offline,
+            black: b.to_string(),
+            white: w.to_string(),
+            my_side,
+            black_discs: black,
+            white_discs: white,
+            note: String::new(),
+            ending: None,
+            start: String::new(),
+            moves: String::new(),
+        }
+    }
+
+    #[test]
+    fn tallies_by_color_opponent_and_streak() {
+        let stats = compute(&[
+            entry(Some(BLACK), "Bot", 40, 24),
+            entry(Some(WHITE), "bob", 20, 44),
+            entry(None, "bob", 10, 54),
+            entry(Some(WHITE), "Bot", 32, 32),
+            entry(Some(BLACK), "bob", 33, 31),
+        ]);
+
+        assert_eq!(stats.overall.games(), 4);
+        assert_eq!(stats.overall.wins, 3);
+        assert_eq!(stats.as_white.draws, 1);
+        assert_eq!(stats.by_opponent["bob"].wins, 2);
+        assert_eq!(stats.by_opponent["Bot"].avg_margin(), 8.0);
+        assert_eq!(stats.longest_streak, 2);
+        assert_eq!(stats.current_streak, 1);
+
+        let mut flagged = entry(Some(BLACK), "bob", 40, 20);
+        flagged.ending = Some(Ending::Lost(BLACK));
+        assert_eq!(compute(&[flagged]).overall.losses, 1);
+    }
+}
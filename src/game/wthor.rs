@@ -0,0 +1,233 @@
+use std::{fs, path::Path};
+
+use super::{position::Position, record::GameRecord};
+
+const HEADER_LEN: usize = 16;
+const GAME_LEN: usize = 68;
+const PLAYER_LEN: usize = 20;
+const TOURNAMENT_LEN: usize = 26;
+
+/// The 16 byte header shared by `.wtb`, `.jou` and `.trn` files.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WthorHeader {
+    pub created: (u16, u8, u8),
+    pub games: u32,
+    pub records: u16,
+    pub year: u16,
+    pub board_size: u8,
+}
+
+/// One game record of a `.wtb` file, names still as indices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WthorGame {
+    pub tournament: u16,
+    pub black: u16,
+    pub white: u16,
+    pub black_score: u8,
+    pub theoretical_score: u8,
+    pub moves: Vec<(usize, usize)>,
+}
+
+fn u16_at(bytes: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([bytes[off], bytes[off + 1]])
+}
+
+fn u32_at(bytes: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([bytes[off], bytes[off + 1], bytes[off + 2], bytes[off + 3]])
+}
+
+pub fn parse_header(bytes: &[u8]) -> Result<WthorHeader, String> {
+    if bytes.len() < HEADER_LEN {
+        return Err("file too short for a WTHOR header".to_string());
+    }
+    Ok(WthorHeader {
+        created: (bytes[0] as u16 * 100 + bytes[1] as u16, bytes[2], bytes[3]),
+        games: u32_at(bytes, 4),
+        records: u16_at(bytes, 8),
+        year: u16_at(bytes, 10),
+        board_size: bytes[12],
+    })
+}
+
+/// Parses a `.wtb` game file. Records with a move off the board are left
+/// out, and how many were is returned with the games.
+pub fn parse_games(bytes: &[u8]) -> Result<(WthorHeader, Vec<WthorGame>, usize), String> {
+    let header = parse_header(bytes)?;
+    if header.board_size != 0 && header.board_size != 8 {
+        return Err(format!("unsupported board size {}", header.board_size));
+    }
+
+    let body = &bytes[HEADER_LEN..];
+    let count = (header.games as usize).min(body.len() / GAME_LEN);
+    let mut games = Vec::with_capacity(count);
+    let mut skipped = 0;
+
+    for rec in body.chunks_exact(GAME_LEN).take(count) {
+        let Some(moves) = parse_moves(&rec[8..]) else {
+            skipped += 1;
+            continue;
+        };
+        games.push(WthorGame {
+            tournament: u16_at(rec, 0),
+            black: u16_at(rec, 2),
+            white: u16_at(rec, 4),
+            black_score: rec[6],
+            theoretical_score: rec[7],
+            moves,
+        });
+    }
+    Ok((header, games, skipped))
+}
+
+/// Moves are stored as `10 * row + col`, both from 1, until a zero byte.
+fn parse_moves(bytes: &[u8]) -> Option<Vec<(usize, usize)>> {
+    let mut moves = vec![];
+    for b in bytes.iter().take_while(|b| **b != 0) {
+        let (row, col) = (*b / 10, *b % 10);
+        if !(1..=8).contains(&row) || !(1..=8).contains(&col) {
+            return None;
+        }
+        moves.push((row as usize - 1, col as usize - 1));
+    }
+    Some(moves)
+}
+
+/// Parses a `.jou` (players) or `.trn` (tournaments) name file.
+pub fn parse_names(bytes: &[u8], record_len: usize) -> Result<Vec<String>, String> {
+    parse_header(bytes)?;
+    Ok(bytes[HEADER_LEN..]
+        .chunks_exact(record_len)
+        .map(|rec| {
+            rec.iter()
+                .take_while(|b| **b != 0)
+                .map(|b| *b as char)
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .collect())
+}
+
+pub fn parse_players(bytes: &[u8]) -> Result<Vec<String>, String> {
+    parse_names(bytes, PLAYER_LEN)
+}
+
+pub fn parse_tournaments(bytes: &[u8]) -> Result<Vec<String>, String> {
+    parse_names(bytes, TOURNAMENT_LEN)
+}
+
+impl WthorGame {
+    /// Replays the moves from the standard start, inferring passes.
+    pub fn to_record(&self) -> Result<GameRecord, String> {
+        let mut record = GameRecord::new(Position::initial());
+        for (idx, square) in self.moves.iter().enumerate() {
+            let mut current = record.current();
+            if !current.has_moves() {
+                current.pass();
+            }
+            if !record.play(current.side, *square) {
+                return Err(format!("illegal move at ply {}", idx + 1));
+            }
+        }
+        Ok(record)
+    }
+}
+
+/// Looks for a file next to `wtb` whose extension matches `ext`, ignoring case
+/// (the federation ships `WTHOR.JOU` and `WTHOR.TRN`). One named like `wtb`
+/// wins over the others.
+pub fn find_sibling(wtb: &Path, ext: &str) -> Option<std::path::PathBuf> {
+    let dir = wtb
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let stem = |p: &Path| p.file_stem().map(|s| s.to_string_lossy().to_lowercase());
+    let mut found: Vec<_> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case(ext))
+        })
+        .collect();
+    found.sort();
+    let pick = found.iter().position(|p| stem(p) == stem(wtb)).unwrap_or(0);
+    (!found.is_empty()).then(|| found.swap_remove(pick))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(games: u32, records: u16) -> Vec<u8> {
+        let mut h = vec![20, 24, 1, 15];
+        h.extend(games.to_le_bytes());
+        h.extend(records.to_le_bytes());
+        h.extend(2023u16.to_le_bytes());
+        h.extend([8, 0, 22, 0]);
+        h
+    }
+
+    #[test]
+    fn reads_games_and_names() {
+        let mut wtb = header(1, 0);
+        wtb.extend(3u16.to_le_bytes());
+        wtb.extend(0u16.to_le_bytes());
+        wtb.extend(1u16.to_le_bytes());
+        wtb.extend([36, 34]);
+        let mut moves = vec![56, 64, 33];
+        moves.resize(60, 0);
+        wtb.extend(moves);
+
+        let (info, games, skipped) = parse_games(&wtb).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(info.year, 2023);
+        assert_eq!(info.created, (2024, 1, 15));
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].tournament, 3);
+        assert_eq!(games[0].black_score, 36);
+        assert_eq!(games[0].to_record().unwrap().to_transcript(), "f5d6c3");
+
+        let mut jou = header(0, 2);
+        for name in ["Tastet Marc", "Shaman Brian"] {
+            let mut rec = name.as_bytes().to_vec();
+            rec.resize(PLAYER_LEN, 0);
+            jou.extend(rec);
+        }
+        assert_eq!(
+            parse_players(&jou).unwrap(),
+            vec!["Tastet Marc", "Shaman Brian"]
+        );
+    }
+
+    #[test]
+    fn skips_records_with_moves_off_the_board() {
+        let mut wtb = header(3, 0);
+        for first in [56, 90, 64] {
+            wtb.extend([0; 8]);
+            let mut moves = vec![first];
+            moves.resize(60, 0);
+            wtb.extend(moves);
+        }
+
+        let (_, games, skipped) = parse_games(&wtb).unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[1].moves, vec![(5, 3)]);
+    }
+
+    #[test]
+    fn prefers_names_next_to_the_games_they_belong_to() {
+        let dir = std::env::temp_dir().join(format!("othello-rs-wthor-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["WTHOR.JOU", "WTH_2023.wtb", "WTH_2023.jou"] {
+            fs::write(dir.join(name), []).unwrap();
+        }
+
+        let found = find_sibling(&dir.join("WTH_2023.wtb"), "jou");
+        let other = find_sibling(&dir.join("WTH_2022.wtb"), "jou");
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, Some(dir.join("WTH_2023.jou")));
+        assert_eq!(other, Some(dir.join("WTHOR.JOU")));
+    }
+}
@@ -0,0 +1,18 @@
+//! The game, its screens and the server for online games, shared by the
+//! `othello-rs` and `othello-server` binaries.
+
+pub mod custom_elements;
+pub mod game;
+pub mod menu;
+pub mod server;
+pub mod servers;
+pub mod settings;
+pub mod storage;
+pub mod termin;
+pub mod theme;
+
+use std::{thread, time::Duration};
+
+pub fn sleep(ms: u64) {
+    thread::sleep(Duration::from_millis(ms));
+}
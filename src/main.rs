@@ -1,30 +1,35 @@
-mod custom_elements;
-mod game;
-mod menu;
-mod termin;
-
 use crossterm::{
-    cursor, execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    cursor,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    },
 };
 
-use game::macros::choose_side_win;
-use game::online_lobby::Online;
-use menu::{Menu, Return};
-use std::io::stdout;
-use std::thread;
-use std::time::Duration;
+use othello_rs::custom_elements::choose_side;
+use othello_rs::game::online_lobby::Online;
+use othello_rs::menu::{Menu, Return};
+use othello_rs::{storage, termin};
+use serde::{Deserialize, Serialize};
+use std::io::{stdout, Write};
+use std::path::PathBuf;
+use std::{env, process};
 use termin::crossterm_handler::CrosstermHandler;
 use termin::elements::Text;
+use termin::recorder::Recorder;
 use termin::terminal_window::TerminalHandler;
 use termin::window::{Position::*, Window};
 
-use crate::game::offline_game::play_offline;
-use crate::termin::elements::InputWindow;
-
-fn sleep(ms: u64) {
-    thread::sleep(Duration::from_millis(ms));
-}
+use othello_rs::game::database::{import_screen, openings_screen, search_screen};
+use othello_rs::game::history::history_screen;
+use othello_rs::game::offline_game::{load_offline, play_offline};
+use othello_rs::game::puzzle::play_puzzles;
+use othello_rs::game::replay::replay_game;
+use othello_rs::game::stats::stats_screen;
+use othello_rs::servers::{choose_server, Server, Servers};
+use othello_rs::settings::settings_screen;
+use othello_rs::termin::elements::InputWindow;
 
 fn change_name(terminal: &mut TerminalHandler, ctx: &mut Ctx) -> Return {
     let name = terminal.handle_input(|handler, root| -> String {
@@ -52,24 +57,96 @@ fn change_name(terminal: &mut TerminalHandler, ctx: &mut Ctx) -> Return {
         new_name
     });
 
-    ctx.name = name;
+    if !name.trim().is_empty() {
+        ctx.name = name;
+        _ = storage::save_json(PROFILE_FILE, ctx);
+    }
     Return::None
 }
 
+const PROFILE_FILE: &str = "profile.json";
+
+#[derive(Serialize, Deserialize)]
 struct Ctx {
     name: String,
+    /// Where online games are played, see `Servers::current`.
+    #[serde(skip)]
+    server: Server,
 }
 
-fn main() {
-    enable_raw_mode().unwrap();
-    execute!(stdout(), EnterAlternateScreen, cursor::Hide).unwrap();
+impl Default for Ctx {
+    fn default() -> Self {
+        Ctx {
+            name: "Player".to_string(),
+            server: Server::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    record: Option<PathBuf>,
+    /// Server profile name or URL.
+    server: Option<String>,
+}
 
-    let mut terminal = termin::root(CrosstermHandler::new(stdout()));
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => {
+                    parsed.record = Some(args.next().ok_or("--record needs a file")?.into());
+                }
+                "--server" => {
+                    parsed.server =
+                        Some(args.next().ok_or("--server needs a profile name or URL")?);
+                }
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+const USAGE: &str = "usage: othello-rs [--record <file.cast>] [--server <profile|url>]";
 
-    let mut game_ctx = Ctx {
-        name: "Player".to_string(),
+fn main() {
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+    let server = Servers::load()
+        .current(args.server.as_deref())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        });
+    let screen: Box<dyn Write> = Box::new(stdout());
+    let out = match &args.record {
+        Some(path) => {
+            let size = terminal::size().unwrap_or((80, 24));
+            Recorder::create(screen, path, size).unwrap_or_else(|e| {
+                eprintln!("unable to record to {}: {}", path.display(), e);
+                process::exit(1);
+            })
+        }
+        None => Recorder::new(screen),
     };
 
+    enable_raw_mode().unwrap();
+    let mut terminal = termin::root(CrosstermHandler::new(out));
+    execute!(
+        terminal.handler,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        cursor::Hide
+    )
+    .unwrap();
+
+    let mut game_ctx: Ctx = storage::load_json(PROFILE_FILE);
+    game_ctx.server = server;
+
     let mut menu_map = Menu::<Ctx>::new("Main Menu")
         .routine(&|menu, ctx| {
             let mut s = "Welome ".to_string();
@@ -84,43 +161,111 @@ fn main() {
                     "offline",
                     Menu::<Ctx>::new("Offline")
                         .action("1 player", &|terminal, ctx| -> Return {
-                            play_offline(terminal, ctx, 1)
+                            play_offline(terminal, &ctx.name, 1)
                         })
                         .action("2 player", &|terminal, ctx| -> Return {
-                            play_offline(terminal, ctx, 2)
+                            play_offline(terminal, &ctx.name, 2)
+                        })
+                        .action("load game", &|terminal, ctx| -> Return {
+                            load_offline(terminal, &ctx.name)
+                        })
+                        .action("replay game", &|terminal, ctx| -> Return {
+                            replay_game(terminal, ctx)
                         })
                         .back("back"),
                 )
                 .sub_menu(
                     "online",
                     Menu::<Ctx>::new("Online")
+                        .routine(&|menu, ctx| {
+                            menu.heading
+                                .set_text(&format!("Online on {}", ctx.server.name));
+                            menu.heading.width_fit();
+                        })
                         .action("create game", &|terminal, ctx| -> Return {
-                            let host_side = choose_side_win!(
-                                terminal,
-                                "Create Game Online",
-                                "Choose your side:"
-                            );
+                            let Some(host_side) =
+                                choose_side(terminal, "Create Game Online", "Choose your side:")
+                            else {
+                                return Return::None;
+                            };
                             Online::new(&mut terminal.root)
                                 .set_player(host_side, &ctx.name)
+                                .set_server(&ctx.server)
                                 .create_and_start(terminal);
                             Return::None
                         })
                         .action("join game", &|terminal, ctx| -> Return {
                             Online::new(&mut terminal.root)
                                 .set_player(0 as char, &ctx.name)
+                                .set_server(&ctx.server)
                                 .join_and_start(terminal);
                             Return::None
                         })
+                        .action("host on LAN", &|terminal, ctx| -> Return {
+                            let Some(host_side) =
+                                choose_side(terminal, "Host Game on LAN", "Choose your side:")
+                            else {
+                                return Return::None;
+                            };
+                            Online::new(&mut terminal.root)
+                                .set_player(host_side, &ctx.name)
+                                .host_lan_and_start(terminal);
+                            Return::None
+                        })
+                        .action("join by address", &|terminal, ctx| -> Return {
+                            Online::new(&mut terminal.root)
+                                .set_player(0 as char, &ctx.name)
+                                .join_address_and_start(terminal);
+                            Return::None
+                        })
+                        .action("server", &|terminal, ctx| -> Return {
+                            if let Some(server) = choose_server(terminal, &ctx.server) {
+                                ctx.server = server;
+                            }
+                            Return::None
+                        })
                         .back("back"),
                 )
                 .back("back"),
         )
+        .action("puzzles", &|terminal, ctx| -> Return {
+            play_puzzles(terminal, ctx)
+        })
+        .action("history", &|terminal, ctx| -> Return {
+            history_screen(terminal, ctx)
+        })
+        .action("stats", &|terminal, ctx| -> Return {
+            stats_screen(terminal, ctx)
+        })
+        .sub_menu(
+            "database",
+            Menu::<Ctx>::new("Game Database")
+                .action("search player", &|terminal, ctx| -> Return {
+                    search_screen(terminal, ctx)
+                })
+                .action("opening stats", &|terminal, ctx| -> Return {
+                    openings_screen(terminal, ctx)
+                })
+                .action("import wthor", &|terminal, ctx| -> Return {
+                    import_screen(terminal, ctx)
+                })
+                .back("back"),
+        )
+        .action("settings", &|terminal, ctx| -> Return {
+            settings_screen(terminal, ctx)
+        })
         .action("change name", &change_name)
         .back("quit");
 
     menu_map.run(&mut terminal, &mut game_ctx);
 
-    execute!(stdout(), cursor::Show, LeaveAlternateScreen).unwrap();
+    execute!(
+        terminal.handler,
+        cursor::Show,
+        DisableMouseCapture,
+        LeaveAlternateScreen
+    )
+    .unwrap();
     disable_raw_mode().unwrap();
 }
 
@@ -130,11 +275,28 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn parses_record_flag() {
+        let args = |a: &[&str]| Args::parse(a.iter().map(|s| s.to_string()));
+        assert_eq!(args(&[]).unwrap(), Args::default());
+        assert_eq!(
+            args(&["--record", "game.cast"]).unwrap().record,
+            Some(PathBuf::from("game.cast"))
+        );
+        assert!(args(&["--record"]).is_err());
+        assert_eq!(
+            args(&["--server", "team"]).unwrap().server.as_deref(),
+            Some("team")
+        );
+        assert!(args(&["--server"]).is_err());
+        assert!(args(&["--fast"]).is_err());
+    }
+
     #[test]
     fn scroll() {
         enable_raw_mode().unwrap();
         execute!(stdout(), EnterAlternateScreen, cursor::Hide).unwrap();
-        let mut terminal = termin::root(CrosstermHandler::new(stdout()));
+        let mut terminal = termin::root(CrosstermHandler::new(Recorder::new(stdout())));
 
         let mut win = terminal.root.new_child(
             Window::default()
@@ -189,9 +351,11 @@ mod tests {
     fn change_name_fn() {
         enable_raw_mode().unwrap();
         execute!(stdout(), EnterAlternateScreen, cursor::Hide).unwrap();
-        let mut terminal = termin::root(CrosstermHandler::new(stdout()));
+        let mut terminal: TerminalHandler =
+            termin::root(CrosstermHandler::new(Recorder::new(Box::new(stdout()))));
         let mut game_ctx = Ctx {
             name: "Player".to_string(),
+            ..Ctx::default()
         };
 
         change_name(&mut terminal, &mut game_ctx);
@@ -1,19 +1,20 @@
-use std::{io::Stdout, thread, time::Duration};
+use std::{thread, time::Duration};
 
 use crossterm::{
-    event::{Event, KeyCode},
+    event::{Event, KeyCode, MouseButton, MouseEventKind},
     style::Color,
 };
 
 use crate::termin::{
     elements::{Rectangle, Text},
-    terminal_window::{Terminal, TerminalHandler},
+    terminal_window::TerminalHandler,
     window::{Position::*, Window, WindowRef},
 };
+use crate::theme::Theme;
 
 pub struct Action<'a, T> {
     label: &'a str,
-    action_fn: &'a dyn Fn(&mut Terminal<Stdout>, &mut T) -> Return,
+    action_fn: &'a dyn Fn(&mut TerminalHandler, &mut T) -> Return,
 }
 
 pub struct Menu<'a, T> {
@@ -59,7 +60,7 @@ impl<'a, T> Menu<'a, T> {
     pub fn action(
         mut self,
         label: &'a str,
-        action_fn: &'a dyn Fn(&mut Terminal<Stdout>, &mut T) -> Return,
+        action_fn: &'a dyn Fn(&mut TerminalHandler, &mut T) -> Return,
     ) -> Self {
         self.list
             .push(MenuItem::<'a>::Action(Action { label, action_fn }));
@@ -96,6 +97,7 @@ impl<'a, T> Menu<'a, T> {
             menu_win.new_child(Window::default().size(40, (self.list.len() * 2 - 1) as u32));
 
         let mut opt = Text::default().start_text((1, 0));
+        let theme = Theme::load();
         let run_routine = |s: &mut Self, ctx: &mut T| match s.routine_fn {
             Some(r) => r(s, ctx),
             None => (),
@@ -135,9 +137,9 @@ impl<'a, T> Menu<'a, T> {
                 opt.set_position(options_win.rect(), CenterH);
 
                 if idx == self.cursor as usize {
-                    let bg = Rectangle::from_rect(opt.rect()).bg(Color::Green);
+                    let bg = Rectangle::from_rect(opt.rect()).bg(theme.frame);
                     options_win.draw_element(&bg);
-                    opt.set_fg(Color::Black);
+                    opt.set_fg(theme.frame_text);
                 }
 
                 options_win.draw_element(&opt);
@@ -148,7 +150,7 @@ impl<'a, T> Menu<'a, T> {
             menu_win.render();
             terminal.refresh().unwrap();
 
-            match terminal.event() {
+            let selected = match terminal.event() {
                 Event::Key(k) => match k.code {
                     KeyCode::Esc => {
                         RETURN!(Back);
@@ -158,6 +160,7 @@ impl<'a, T> Menu<'a, T> {
                         if self.cursor == self.list.len() as u32 {
                             self.cursor = 0;
                         }
+                        false
                     }
                     KeyCode::Up => {
                         if self.cursor == 0 {
@@ -165,47 +168,58 @@ impl<'a, T> Menu<'a, T> {
                         } else {
                             self.cursor -= 1;
                         }
+                        false
                     }
-                    KeyCode::Enter => {
-                        let menu_item = &mut self.list[self.cursor as usize];
-                        match menu_item {
-                            MenuItem::Action(a) => match (a.action_fn)(terminal, ctx) {
-                                Return::ToRoot => {
-                                    if self.id != 0 {
-                                        RETURN!(ToRoot);
-                                    }
-                                }
-                                Return::Back => {
-                                    RETURN!(None);
-                                }
-                                Return::All => {
-                                    RETURN!(All);
-                                }
-                                Return::None => (),
-                            },
-                            MenuItem::SubMenu(sm) => match sm.menu.run(terminal, ctx) {
-                                Return::ToRoot => {
-                                    if self.id != 0 {
-                                        RETURN!(ToRoot);
-                                    }
-                                }
-                                Return::Back => {
-                                    RETURN!(None);
-                                }
-                                Return::All => {
-                                    RETURN!(All);
-                                }
-                                Return::None => (),
-                            },
-                        }
-                        menu_win.clear();
-                        run_routine(self, ctx);
-                        self.heading.set_position(menu_win.rect(), CenterH);
-                        menu_win.draw_element(&self.heading);
+                    KeyCode::Enter => true,
+                    _ => false,
+                },
+                // options sit on every other line of `options_win`
+                Event::Mouse(m) => match options_win.hit(m.column, m.row) {
+                    Some((_, y)) if y % 2 == 0 && ((y / 2) as usize) < self.list.len() => {
+                        self.cursor = y / 2;
+                        m.kind == MouseEventKind::Down(MouseButton::Left)
                     }
-                    _ => (),
+                    _ => false,
                 },
-                _ => (),
+                _ => false,
+            };
+
+            if selected {
+                let menu_item = &mut self.list[self.cursor as usize];
+                match menu_item {
+                    MenuItem::Action(a) => match (a.action_fn)(terminal, ctx) {
+                        Return::ToRoot => {
+                            if self.id != 0 {
+                                RETURN!(ToRoot);
+                            }
+                        }
+                        Return::Back => {
+                            RETURN!(None);
+                        }
+                        Return::All => {
+                            RETURN!(All);
+                        }
+                        Return::None => (),
+                    },
+                    MenuItem::SubMenu(sm) => match sm.menu.run(terminal, ctx) {
+                        Return::ToRoot => {
+                            if self.id != 0 {
+                                RETURN!(ToRoot);
+                            }
+                        }
+                        Return::Back => {
+                            RETURN!(None);
+                        }
+                        Return::All => {
+                            RETURN!(All);
+                        }
+                        Return::None => (),
+                    },
+                }
+                menu_win.clear();
+                run_routine(self, ctx);
+                self.heading.set_position(menu_win.rect(), CenterH);
+                menu_win.draw_element(&self.heading);
             }
         }
 
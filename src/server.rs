@@ -0,0 +1,624 @@
+//! The server side of the online protocol, so games can be hosted on a LAN
+//! or a VM instead of othellojs. The `othello-server` binary runs it.
+//!
+//! Lobbies are made over http (`POST /api/create-lobby`,
+//! `GET /api/game-info/:id`); everything else happens on the
+//! `/api/join-game/:id` websocket. The server keeps its own copy of every
+//! game and only passes on moves the rules allow.
+
+pub mod http;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures_util::{SinkExt, StreamExt};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{self, UnboundedSender},
+};
+use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+
+use crate::game::{
+    position::{opponent_of, Position, Side, Square, BLACK, EMPTY, WHITE},
+    socket::SocketMsg,
+};
+
+/// How long a player who dropped out of a running game has to come back
+/// before their opponent wins. The client counts the same 20 seconds down.
+pub const RECONNECT_GRACE: Duration = Duration::from_secs(20);
+/// Lobbies nobody ever joined are forgotten after this long.
+const LOBBY_TTL: Duration = Duration::from_secs(60 * 60);
+/// The `data` of events that carry nothing, as the client sends it.
+const NO_DATA: &str = "\"\"";
+
+type Games = Arc<Mutex<HashMap<String, Game>>>;
+type Sender = UnboundedSender<Message>;
+
+struct Seat {
+    name: String,
+    id: String,
+    /// Frames for the player's socket, `None` while they are away.
+    tx: Option<Sender>,
+    /// When they went away, so a grace timer can tell they never came back.
+    away_since: Option<Instant>,
+}
+
+struct Game {
+    host: String,
+    host_side: Side,
+    created: Instant,
+    seats: HashMap<Side, Seat>,
+    /// Set once both players are in.
+    position: Option<Position>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayerInfo {
+    player_name: String,
+    is_reconnect: bool,
+    player_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Move {
+    row_idx: usize,
+    col_idx: usize,
+}
+
+fn frame(event: &str, data: String) -> Message {
+    let msg = SocketMsg {
+        name: event.to_string(),
+        data,
+    };
+    Message::Text(serde_json::to_string(&msg).unwrap())
+}
+
+fn random_id(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+impl Game {
+    fn new(host: String, host_side: Side) -> Self {
+        Self {
+            host,
+            host_side,
+            created: Instant::now(),
+            seats: HashMap::new(),
+            position: None,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.seats.len() == 2
+    }
+
+    fn send(&self, side: Side, event: &str, data: String) {
+        if let Some(tx) = self.seats.get(&side).and_then(|s| s.tx.as_ref()) {
+            _ = tx.send(frame(event, data));
+        }
+    }
+
+    fn send_both(&self, event: &str, data: String) {
+        for side in [BLACK, WHITE] {
+            self.send(side, event, data.clone());
+        }
+    }
+
+    fn lobby_info(&self) -> String {
+        let name = |side| self.seats.get(&side).map_or("", |s| s.name.as_str());
+        json!({"black": name(BLACK), "white": name(WHITE)}).to_string()
+    }
+
+    fn state_for(&self, side: Side) -> String {
+        let pos = self.position.unwrap_or_else(Position::initial);
+        let board = pos
+            .board
+            .map(|row| row.map(|s| if s == EMPTY { 0 } else { s as u8 }));
+        let opponent_online = self
+            .seats
+            .get(&opponent_of(side))
+            .is_some_and(|s| s.tx.is_some());
+        json!({
+            "board": board,
+            "curTurn": pos.side as u8,
+            "blackPoints": pos.count(BLACK),
+            "whitePoints": pos.count(WHITE),
+            "isOpponentOnline": opponent_online,
+        })
+        .to_string()
+    }
+
+    /// Seats a player, or puts one who lost their connection back in their
+    /// seat. The first player in gets the side the lobby was made for.
+    fn join(&mut self, info: PlayerInfo, tx: &Sender) -> Result<Side, String> {
+        let side = if info.is_reconnect {
+            let (side, seat) = self
+                .seats
+                .iter_mut()
+                .find(|(_, s)| s.id == info.player_id && s.tx.is_none())
+                .ok_or("you are not in this game.")?;
+            seat.tx = Some(tx.clone());
+            seat.away_since = None;
+            *side
+        } else {
+            let side = match self.seats.is_empty() {
+                true => self.host_side,
+                false => [BLACK, WHITE]
+                    .into_iter()
+                    .find(|s| !self.seats.contains_key(s))
+                    .ok_or("lobby full!")?,
+            };
+            let seat = Seat {
+                name: info.player_name,
+                id: random_id(16),
+                tx: Some(tx.clone()),
+                away_since: None,
+            };
+            self.seats.insert(side, seat);
+            side
+        };
+
+        let res =
+            json!({"err": false, "msg": "", "playerId": self.seats[&side].id, "side": side as u8});
+        self.send(side, "join-player-info-res", res.to_string());
+        if info.is_reconnect {
+            self.send(opponent_of(side), "opponent-reconnect", NO_DATA.to_string());
+        } else {
+            self.send_both("lobby-info", self.lobby_info());
+            if self.is_full() {
+                self.position = Some(Position::initial());
+                self.send_both("countdown-begin", NO_DATA.to_string());
+            }
+        }
+        Ok(side)
+    }
+
+    /// Plays `side`'s move if it is theirs to make and legal, and tells both
+    /// players what happens next. Returns whether the game is over, `None`
+    /// when the move was refused.
+    fn play(&mut self, side: Side, (row, col): Square) -> Option<bool> {
+        let mut pos = match self.position {
+            Some(pos) if pos.side == side && row < 8 && col < 8 => pos,
+            _ => return None,
+        };
+        if pos.play(row, col).is_empty() {
+            return None;
+        }
+        let moved = json!({"rowIdx": row, "colIdx": col});
+        self.send(opponent_of(side), "opponent-move", moved.to_string());
+
+        if !pos.has_moves() {
+            pos.pass();
+        }
+        self.position = Some(pos);
+        if !pos.has_moves() {
+            // An empty message lets each client say who won from its side.
+            self.send_both("game-over", String::new());
+            return Some(true);
+        }
+        self.send(pos.side, "cur-turn", NO_DATA.to_string());
+        Some(false)
+    }
+}
+
+/// Accepts connections on `listener` until it fails. A `lan` host also
+/// tells anyone who asks at `/api/lan-lobby` which lobby is open, so players
+/// on the same network can join by address alone.
+pub async fn serve(listener: TcpListener, grace: Duration, lan: bool) {
+    let games = Games::default();
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(handle(stream, games.clone(), grace, lan));
+    }
+}
+
+async fn handle(mut stream: TcpStream, games: Games, grace: Duration, lan: bool) {
+    let (method, path) = http::request_line(&stream).await;
+    if let Some(id) = path.strip_prefix("/api/join-game/") {
+        let id = id.to_string();
+        if let Ok(socket) = tokio_tungstenite::accept_async(stream).await {
+            join(socket, id, games, grace).await;
+        }
+        return;
+    }
+
+    let body = http::read_body(&mut stream).await;
+    let (status, response) = respond(&games, &method, &path, &body, lan);
+    http::reply(&mut stream, status, &response).await;
+}
+
+fn respond(
+    games: &Games,
+    method: &str,
+    path: &str,
+    body: &str,
+    lan: bool,
+) -> (&'static str, Value) {
+    let mut games = games.lock().unwrap();
+    match (method, path.strip_prefix("/api/game-info/")) {
+        ("POST", None) if path == "/api/create-lobby" => {
+            #[derive(Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            struct Host {
+                host_name: String,
+                host_side: i8,
+            }
+
+            let host = match serde_json::from_str::<Host>(body) {
+                Ok(host) if [BLACK, WHITE].contains(&(host.host_side as u8 as char)) => host,
+                _ => {
+                    return (
+                        "400 Bad Request",
+                        json!({"err": true, "msg": "invalid lobby."}),
+                    )
+                }
+            };
+            games.retain(|_, game| !game.seats.is_empty() || game.created.elapsed() < LOBBY_TTL);
+            let id = loop {
+                let id = random_id(8);
+                if !games.contains_key(&id) {
+                    break id;
+                }
+            };
+            let game = Game::new(host.host_name, host.host_side as u8 as char);
+            games.insert(id.clone(), game);
+            ("200 OK", json!({"err": false, "msg": "", "gameId": id}))
+        }
+        ("GET", Some(id)) => match games.get(id) {
+            Some(game) => (
+                "200 OK",
+                json!({"err": false, "msg": "", "lobbyName": game.host, "isLobbyFull": game.is_full()}),
+            ),
+            None => (
+                "404 Not Found",
+                json!({"err": true, "msg": "game not found"}),
+            ),
+        },
+        ("GET", None) if lan && path == "/api/lan-lobby" => {
+            let open = games
+                .iter()
+                .filter(|(_, game)| !game.is_full())
+                .max_by_key(|(_, game)| game.created);
+            match open {
+                Some((id, _)) => ("200 OK", json!({"err": false, "msg": "", "gameId": id})),
+                None => (
+                    "404 Not Found",
+                    json!({"err": true, "msg": "nobody is hosting a game there."}),
+                ),
+            }
+        }
+        _ => ("404 Not Found", json!({"err": true, "msg": "not found"})),
+    }
+}
+
+async fn join(socket: WebSocketStream<TcpStream>, id: String, games: Games, grace: Duration) {
+    let (mut sink, mut stream) = socket.split();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let writer = tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            if sink.send(msg).await.is_err() {
+                break;
+            }
+        }
+        _ = sink.close().await;
+    });
+
+    let found = games.lock().unwrap().contains_key(&id);
+    _ = tx.send(frame("game-verified", found.to_string()));
+    let mut seat = None;
+    if found {
+        while let Some(Ok(msg)) = stream.next().await {
+            let Message::Text(text) = msg else { continue };
+            let Ok(msg) = SocketMsg::from(text) else {
+                continue;
+            };
+            if !on_message(&games, &id, &mut seat, &tx, msg) {
+                break;
+            }
+        }
+    }
+
+    if let Some(side) = seat {
+        leave(&games, &id, side, grace);
+    }
+    drop(tx);
+    _ = writer.await;
+}
+
+/// Handles one event of the player in `seat`. Returns false once their game
+/// is gone.
+fn on_message(
+    games: &Games,
+    id: &str,
+    seat: &mut Option<Side>,
+    tx: &Sender,
+    msg: SocketMsg,
+) -> bool {
+    let mut games = games.lock().unwrap();
+    let Some(game) = games.get_mut(id) else {
+        return false;
+    };
+    match (msg.event_name(), *seat) {
+        ("join-player-info", None) => {
+            let joined = msg
+                .parse::<PlayerInfo>()
+                .map_err(|e| e.to_string())
+                .and_then(|info| game.join(info, tx));
+            match joined {
+                Ok(side) => *seat = Some(side),
+                Err(e) => {
+                    let res = json!({"err": true, "msg": e});
+                    _ = tx.send(frame("join-player-info-res", res.to_string()));
+                }
+            }
+        }
+        ("game-state", Some(side)) => game.send(side, "game-state-res", game.state_for(side)),
+        ("move", Some(side)) => {
+            let played = msg
+                .parse::<Move>()
+                .ok()
+                .and_then(|m| game.play(side, (m.row_idx, m.col_idx)));
+            match played {
+                Some(true) => _ = games.remove(id),
+                Some(false) => (),
+                // The client already shows the move; put its board back.
+                None => game.send(side, "game-state-res", game.state_for(side)),
+            }
+        }
+        ("chat-msg", Some(side)) => game.send(opponent_of(side), "chat-msg", msg.data),
+        _ => (),
+    }
+    true
+}
+
+/// Frees the seat of a player still in the lobby. One who leaves a running
+/// game keeps it for `grace`, after which their opponent wins.
+fn leave(games: &Games, id: &str, side: Side, grace: Duration) {
+    let mut all = games.lock().unwrap();
+    let Some(game) = all.get_mut(id) else {
+        return;
+    };
+    if game.position.is_none() {
+        game.seats.remove(&side);
+        match game.seats.is_empty() {
+            true => _ = all.remove(id),
+            false => game.send_both("lobby-info", game.lobby_info()),
+        }
+        return;
+    }
+
+    let since = Instant::now();
+    if let Some(seat) = game.seats.get_mut(&side) {
+        seat.tx = None;
+        seat.away_since = Some(since);
+    }
+    if game.seats.values().all(|s| s.tx.is_none()) {
+        all.remove(id);
+        return;
+    }
+    game.send(
+        opponent_of(side),
+        "wait-for-opponent-reconnect",
+        NO_DATA.to_string(),
+    );
+
+    let (games, id) = (games.clone(), id.to_string());
+    tokio::spawn(async move {
+        tokio::time::sleep(grace).await;
+        let mut all = games.lock().unwrap();
+        let gone = all
+            .get(&id)
+            .and_then(|game| game.seats.get(&side))
+            .is_some_and(|seat| seat.away_since == Some(since));
+        if gone {
+            all[&id].send(opponent_of(side), "game-over", "opponent left.".to_string());
+            all.remove(&id);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use tokio::{runtime::Runtime, time::timeout};
+    use tokio_tungstenite::MaybeTlsStream;
+
+    use super::*;
+
+    struct Player(WebSocketStream<MaybeTlsStream<TcpStream>>);
+
+    impl Player {
+        async fn connect(addr: SocketAddr, id: &str) -> Self {
+            let url = format!("ws://{}/api/join-game/{}", addr, id);
+            let (socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+            Self(socket)
+        }
+
+        /// Connects and sits down, returning the `join-player-info-res`.
+        async fn join(addr: SocketAddr, id: &str, name: &str, player_id: &str) -> (Self, Value) {
+            let mut player = Self::connect(addr, id).await;
+            assert_eq!(player.expect("game-verified").await, "true");
+            let info = json!({"playerName": name, "side": 0, "isReconnect": !player_id.is_empty(), "playerId": player_id});
+            player.emit("join-player-info", info).await;
+            let res = player.expect("join-player-info-res").await;
+            (player, serde_json::from_str(&res).unwrap())
+        }
+
+        async fn emit(&mut self, event: &str, data: Value) {
+            let msg = json!({"name": event, "data": data.to_string()});
+            self.0.send(Message::Text(msg.to_string())).await.unwrap();
+        }
+
+        /// The data of the next event, which has to be `event`.
+        async fn expect(&mut self, event: &str) -> String {
+            loop {
+                let next = timeout(Duration::from_secs(2), self.0.next()).await;
+                if let Ok(Some(Ok(Message::Text(text)))) = next {
+                    let msg = SocketMsg::from(text).unwrap();
+                    assert_eq!(msg.name, event);
+                    return msg.data;
+                }
+                assert!(next.is_ok(), "timed out waiting for {}", event);
+            }
+        }
+    }
+
+    async fn start(grace: Duration) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, grace, false));
+        addr
+    }
+
+    async fn get(addr: SocketAddr, path: &str) -> Value {
+        let url = format!("http://{}{}", addr, path);
+        reqwest::get(url).await.unwrap().json().await.unwrap()
+    }
+
+    /// A lobby for Ann on white with Bob on black, counted down.
+    async fn seated(addr: SocketAddr) -> (String, Player, Player, String) {
+        let url = format!("http://{}/api/create-lobby", addr);
+        let created: Value = reqwest::Client::new()
+            .post(url)
+            .json(&json!({"hostName": "Ann", "hostSide": WHITE as i8}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let id = created["gameId"].as_str().unwrap().to_string();
+
+        let (mut ann, res) = Player::join(addr, &id, "Ann", "").await;
+        assert_eq!(res["side"], WHITE as u8);
+        ann.expect("lobby-info").await;
+        let (mut bob, res) = Player::join(addr, &id, "Bob", "").await;
+        assert_eq!(res["side"], BLACK as u8);
+        let lobby = json!({"black": "Bob", "white": "Ann"}).to_string();
+        for player in [&mut ann, &mut bob] {
+            assert_eq!(player.expect("lobby-info").await, lobby);
+            player.expect("countdown-begin").await;
+        }
+        let bob_id = res["playerId"].as_str().unwrap().to_string();
+        (id, ann, bob, bob_id)
+    }
+
+    #[test]
+    fn plays_a_game_by_the_rules() {
+        Runtime::new().unwrap().block_on(async {
+            let addr = start(RECONNECT_GRACE).await;
+            assert_eq!(get(addr, "/api/game-info/nope").await["err"], true);
+            let (id, mut ann, mut bob, _) = seated(addr).await;
+
+            let info = get(addr, &format!("/api/game-info/{}", id)).await;
+            assert_eq!(
+                (&info["lobbyName"], &info["isLobbyFull"]),
+                (&json!("Ann"), &json!(true))
+            );
+
+            bob.emit("game-state", json!("")).await;
+            let state: Value = serde_json::from_str(&bob.expect("game-state-res").await).unwrap();
+            assert_eq!(state["curTurn"], BLACK as u8);
+            assert_eq!(state["board"][3][3], WHITE as u8);
+            assert_eq!(state["board"][0][0], 0);
+            assert_eq!(state["isOpponentOnline"], true);
+
+            ann.emit("move", json!({"rowIdx": 2, "colIdx": 4})).await;
+            let state: Value = serde_json::from_str(&ann.expect("game-state-res").await).unwrap();
+            assert_eq!(state["curTurn"], BLACK as u8);
+            assert_eq!(state["board"][2][4], 0);
+            bob.emit("move", json!({"rowIdx": 0, "colIdx": 0})).await;
+            bob.expect("game-state-res").await;
+            bob.emit("move", json!({"rowIdx": 4, "colIdx": 5})).await;
+            let moved = json!({"rowIdx": 4, "colIdx": 5}).to_string();
+            assert_eq!(ann.expect("opponent-move").await, moved);
+            ann.expect("cur-turn").await;
+
+            ann.emit("chat-msg", json!("gl")).await;
+            assert_eq!(bob.expect("chat-msg").await, "\"gl\"");
+        });
+    }
+
+    #[test]
+    fn holds_a_seat_for_the_grace_period() {
+        Runtime::new().unwrap().block_on(async {
+            let grace = Duration::from_millis(300);
+            let addr = start(grace).await;
+            let (id, mut ann, bob, bob_id) = seated(addr).await;
+
+            drop(bob);
+            ann.expect("wait-for-opponent-reconnect").await;
+            let (mut bob, res) = Player::join(addr, &id, "Bob", &bob_id).await;
+            assert_eq!(res["side"], BLACK as u8);
+            ann.expect("opponent-reconnect").await;
+
+            tokio::time::sleep(grace * 2).await;
+            bob.emit("game-state", json!("")).await;
+            bob.expect("game-state-res").await;
+
+            drop(ann);
+            bob.expect("wait-for-opponent-reconnect").await;
+            assert_eq!(bob.expect("game-over").await, "opponent left.");
+            assert_eq!(
+                get(addr, &format!("/api/game-info/{}", id)).await["err"],
+                true
+            );
+        });
+    }
+
+    #[test]
+    fn only_lan_hosts_list_their_open_lobby() {
+        let games = Games::default();
+        let lobby = |lan| respond(&games, "GET", "/api/lan-lobby", "", lan).1;
+        assert_eq!(lobby(true)["err"], true);
+
+        let host = json!({"hostName": "Ann", "hostSide": BLACK as i8}).to_string();
+        let created = respond(&games, "POST", "/api/create-lobby", &host, true).1;
+        assert_eq!(lobby(true)["gameId"], created["gameId"]);
+        assert_eq!(lobby(false)["msg"], "not found");
+    }
+
+    #[test]
+    fn ends_the_game_when_nobody_can_move() {
+        let mut game = Game::new("Ann".to_string(), BLACK);
+        let mut inboxes = vec![];
+        for side in [BLACK, WHITE] {
+            let (tx, rx) = mpsc::unbounded_channel();
+            let seat = Seat {
+                name: String::new(),
+                id: String::new(),
+                tx: Some(tx),
+                away_since: None,
+            };
+            game.seats.insert(side, seat);
+            inboxes.push(rx);
+        }
+        let board = format!("-OX{}", "X".repeat(61));
+        game.position = Some(Position::parse(&format!("{} X", board)).unwrap());
+
+        assert_eq!(game.play(WHITE, (0, 0)), None);
+        assert_eq!(game.play(BLACK, (0, 8)), None);
+        assert_eq!(game.play(BLACK, (0, 0)), Some(true));
+        let mut events = |side: usize| {
+            let mut names = vec![];
+            while let Ok(Message::Text(text)) = inboxes[side].try_recv() {
+                names.push(SocketMsg::from(text).unwrap().name);
+            }
+            names
+        };
+        assert_eq!(events(0), ["game-over"]);
+        assert_eq!(events(1), ["opponent-move", "game-over"]);
+    }
+}
@@ -0,0 +1,65 @@
+//! Just enough http for `server` and the mock othellojs of the tests: read
+//! what a request asks for, then answer it with json or hand the stream to
+//! the websocket handshake.
+
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// The method and path of the request on `stream`, peeked so the stream
+/// can still go to `tokio_tungstenite::accept_async`.
+pub async fn request_line(stream: &TcpStream) -> (String, String) {
+    let mut head = [0; 256];
+    let head = loop {
+        let n = stream.peek(&mut head).await.unwrap_or(0);
+        let text = String::from_utf8_lossy(&head[..n]).to_string();
+        if n == 0 || n == head.len() || text.contains("\r\n") {
+            break text;
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    };
+    let mut words = head.split_whitespace();
+    let method = words.next().unwrap_or("").to_string();
+    (method, words.next().unwrap_or("").to_string())
+}
+
+/// Reads the rest of an http request and returns its body.
+pub async fn read_body(stream: &mut TcpStream) -> String {
+    let mut data = vec![];
+    let mut chunk = [0; 1024];
+    loop {
+        let text = String::from_utf8_lossy(&data).to_string();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let length = head
+                .lines()
+                .filter_map(|l| l.split_once(':'))
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse().ok())
+                .unwrap_or(0);
+            if body.len() >= length {
+                return body.to_string();
+            }
+        }
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return String::new(),
+            Ok(n) => data.extend_from_slice(&chunk[..n]),
+        }
+    }
+}
+
+/// Answers with `status`, e.g. `200 OK`, and `body`, then lets the
+/// connection close.
+pub async fn reply(stream: &mut TcpStream, status: &str, body: &Value) {
+    let body = body.to_string();
+    let reply = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    _ = stream.write_all(reply.as_bytes()).await;
+}
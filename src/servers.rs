@@ -0,0 +1,313 @@
+use std::{
+    env,
+    net::{IpAddr, UdpSocket},
+};
+
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{custom_elements::ListScreen, storage, termin::terminal_window::TerminalHandler};
+
+const SERVERS_FILE: &str = "servers.json";
+/// Picks a server by profile name or URL, like `--server`.
+pub const SERVER_ENV: &str = "OTHELLO_SERVER";
+/// Where a game hosted on the LAN listens, unless the address says otherwise.
+pub const LAN_PORT: u16 = 3000;
+/// Addresses `lan_ip` routes towards to find the outgoing interface.
+const ROUTES: [&str; 4] = [
+    "8.8.8.8:80",
+    "192.168.0.1:80",
+    "10.0.0.1:80",
+    "172.16.0.1:80",
+];
+
+/// An othellojs instance. `url` is its http(s) address; the websocket
+/// address is derived from it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Server {
+    pub name: String,
+    pub url: String,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self {
+            name: "othellojs".to_string(),
+            url: "https://othellojs.onrender.com".to_string(),
+        }
+    }
+}
+
+impl Server {
+    /// A profile for a URL given on the command line, named after its host.
+    fn from_url(url: &str) -> Result<Self, String> {
+        let server = Self {
+            name: String::new(),
+            url: url.trim_end_matches('/').to_string(),
+        };
+        match server.url.split_once("://") {
+            Some(("http" | "https", host)) if !host.is_empty() => Ok(Self {
+                name: host.to_string(),
+                ..server
+            }),
+            _ => Err(format!("'{}' is not an http(s) URL", url)),
+        }
+    }
+
+    /// A game hosted on the LAN at `host` or `host:port`.
+    pub fn lan(address: &str) -> Result<Self, String> {
+        let address = address.trim();
+        let address = match address.rsplit_once(':') {
+            _ if address.is_empty() || address.contains('/') => None,
+            Some((host, port)) => {
+                (!host.is_empty() && port.parse::<u16>().is_ok()).then(|| address.to_string())
+            }
+            None => Some(format!("{}:{}", address, LAN_PORT)),
+        }
+        .ok_or(format!("'{}' is not an ip:port address.", address))?;
+        Ok(Self {
+            name: "LAN".to_string(),
+            url: format!("http://{}", address),
+        })
+    }
+
+    fn base(&self) -> &str {
+        self.url.trim_end_matches('/')
+    }
+
+    /// The address without its scheme, so http and https links match.
+    fn host(&self) -> &str {
+        self.base()
+            .split_once("://")
+            .map_or(self.base(), |(_, host)| host)
+    }
+
+    pub fn http(&self, path: &str) -> String {
+        self.base().to_string() + path
+    }
+
+    pub fn ws(&self, path: &str) -> String {
+        let base = self.base();
+        match base.split_once("://") {
+            Some(("http", host)) => format!("ws://{}{}", host, path),
+            Some((_, host)) => format!("wss://{}{}", host, path),
+            None => format!("wss://{}{}", base, path),
+        }
+    }
+
+    /// The link others paste to join game `id`.
+    pub fn join_link(&self, id: &str) -> String {
+        self.http(&format!("/join-game/{}", id))
+    }
+}
+
+/// Server profiles kept in `servers.json`, with the one picked last.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Servers {
+    pub selected: String,
+    pub servers: Vec<Server>,
+}
+
+impl Default for Servers {
+    fn default() -> Self {
+        let server = Server::default();
+        Self {
+            selected: server.name.clone(),
+            servers: vec![server],
+        }
+    }
+}
+
+impl Servers {
+    pub fn load() -> Self {
+        storage::load_json(SERVERS_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::save_json(SERVERS_FILE, self)
+    }
+
+    /// The profile called `choice`, or a new one when `choice` is a URL.
+    pub fn find(&self, choice: &str) -> Result<Server, String> {
+        match self.servers.iter().find(|s| s.name == choice) {
+            Some(server) => Ok(server.clone()),
+            None if choice.contains("://") => Server::from_url(choice),
+            None => Err(format!("no server profile called '{}'", choice)),
+        }
+    }
+
+    /// The server to play on: `flag` if given, then `OTHELLO_SERVER`, then
+    /// the last one picked in the menu.
+    pub fn current(&self, flag: Option<&str>) -> Result<Server, String> {
+        let env = env::var(SERVER_ENV).ok();
+        self.choose(flag.or(env.as_deref()))
+    }
+
+    fn choose(&self, choice: Option<&str>) -> Result<Server, String> {
+        match choice {
+            Some(choice) => self.find(choice),
+            None => Ok(self.find(&self.selected).unwrap_or_default()),
+        }
+    }
+}
+
+/// Splits a join link into the server it points at and the game id. A bare
+/// id is taken to be on `current`.
+pub fn parse_join_link(
+    link: &str,
+    current: &Server,
+    known: &[Server],
+) -> Result<(Server, String), String> {
+    let link = link.trim().trim_end_matches('/');
+    let (base, id) = match link.rsplit_once("/join-game/") {
+        Some((base, id)) => (Some(base), id),
+        None => (None, link),
+    };
+    if id.is_empty() || id.contains('/') {
+        return Err("invalid link.".to_string());
+    }
+    let server = match base {
+        None => current.clone(),
+        Some(base) => {
+            let host = base.split_once("://").map_or(base, |(_, host)| host);
+            known
+                .iter()
+                .chain([current])
+                .find(|s| s.host() == host)
+                .cloned()
+                .ok_or(format!("{} is not a configured server", base))?
+        }
+    };
+    Ok((server, id.to_string()))
+}
+
+/// This machine's address on the local network, the one others dial to
+/// join a game hosted here. Connecting a UDP socket sends nothing; it only
+/// picks the interface a route goes out of.
+pub fn lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    ROUTES
+        .iter()
+        .filter(|target| socket.connect(target).is_ok())
+        .filter_map(|_| socket.local_addr().ok())
+        .map(|addr| addr.ip())
+        .find(|ip| !ip.is_loopback() && !ip.is_unspecified())
+}
+
+/// Lists the profiles and returns the one picked with enter, remembering it
+/// for the next run.
+pub fn choose_server(terminal: &mut TerminalHandler, current: &Server) -> Option<Server> {
+    let mut servers = Servers::load();
+    if !servers.servers.contains(current) {
+        servers.servers.push(current.clone());
+    }
+    let mut screen = ListScreen::new(terminal);
+    screen.list.set_items(
+        servers
+            .servers
+            .iter()
+            .map(|s| {
+                let mark = if s == current { "*" } else { " " };
+                format!("{} {:<16} {}", mark, s.name, s.url)
+            })
+            .collect(),
+    );
+
+    let heading = format!("Servers (add more in {})", SERVERS_FILE);
+    let chosen = loop {
+        screen.render(terminal, &heading, "up/down: select  enter: use  esc: back");
+        match terminal.getch() {
+            KeyCode::Esc => break None,
+            KeyCode::Enter => break Some(servers.servers[screen.list.cursor()].clone()),
+            k => screen.navigate(k),
+        }
+    };
+    screen.win.delete();
+
+    let server = chosen?;
+    servers.selected = server.name.clone();
+    _ = servers.save();
+    Some(server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn own() -> Server {
+        Server {
+            name: "team".to_string(),
+            url: "http://othello.example.org:8080/".to_string(),
+        }
+    }
+
+    #[test]
+    fn urls_and_profiles() {
+        let server = own();
+        assert_eq!(
+            server.ws("/api/join-game/x"),
+            "ws://othello.example.org:8080/api/join-game/x"
+        );
+        assert_eq!(
+            server.join_link("x"),
+            "http://othello.example.org:8080/join-game/x"
+        );
+        assert_eq!(Server::default().ws(""), "wss://othellojs.onrender.com");
+
+        let servers = Servers {
+            selected: "team".to_string(),
+            servers: vec![Server::default(), own()],
+        };
+        assert_eq!(servers.choose(None).unwrap(), own());
+        assert_eq!(servers.find("othellojs").unwrap(), Server::default());
+        assert_eq!(
+            servers.find("https://localhost:3000/").unwrap().name,
+            "localhost:3000"
+        );
+        assert!(servers.find("nope").is_err());
+        assert!(servers.find("ftp://host").is_err());
+    }
+
+    #[test]
+    fn join_links_from_any_configured_server() {
+        let known = [Server::default(), own()];
+        let current = Server::default();
+
+        let (server, id) = parse_join_link(
+            "https://othello.example.org:8080/join-game/abc/",
+            &current,
+            &known,
+        )
+        .unwrap();
+        assert_eq!((server, id.as_str()), (own(), "abc"));
+
+        let (server, id) = parse_join_link(" abc ", &own(), &known).unwrap();
+        assert_eq!((server, id.as_str()), (own(), "abc"));
+
+        assert!(parse_join_link("https://evil.example/join-game/abc", &current, &known).is_err());
+        assert!(parse_join_link(
+            "https://othellojs.onrender.com/join-game/",
+            &current,
+            &known
+        )
+        .is_err());
+        assert!(parse_join_link("", &current, &known).is_err());
+    }
+
+    #[test]
+    fn lan_addresses() {
+        assert_eq!(
+            Server::lan("192.168.1.20").unwrap().url,
+            "http://192.168.1.20:3000"
+        );
+        assert_eq!(
+            Server::lan(" 10.0.0.7:4000 ").unwrap().ws("/x"),
+            "ws://10.0.0.7:4000/x"
+        );
+        assert!(Server::lan("").is_err());
+        assert!(Server::lan("10.0.0.7:http").is_err());
+        assert!(Server::lan(":4000").is_err());
+        assert!(Server::lan("http://10.0.0.7").is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    custom_elements::ListScreen,
+    menu::Return,
+    storage,
+    termin::terminal_window::TerminalHandler,
+    theme::{Theme, THEMES, THEME_FILE},
+};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimationSpeed {
+    Off,
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl AnimationSpeed {
+    /// Time between two frames of the flip animation, `None` when it is off.
+    pub fn frame_time(self) -> Option<Duration> {
+        match self {
+            AnimationSpeed::Off => None,
+            AnimationSpeed::Slow => Some(Duration::from_millis(150)),
+            AnimationSpeed::Normal => Some(Duration::from_millis(80)),
+            AnimationSpeed::Fast => Some(Duration::from_millis(40)),
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            AnimationSpeed::Off => AnimationSpeed::Slow,
+            AnimationSpeed::Slow => AnimationSpeed::Normal,
+            AnimationSpeed::Normal => AnimationSpeed::Fast,
+            AnimationSpeed::Fast => AnimationSpeed::Off,
+        }
+    }
+}
+
+/// Preferences kept in `settings.json`. Fields missing from the file keep
+/// their defaults, so older files still load.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Arrow keys move over all 64 squares instead of jumping between legal moves.
+    pub free_cursor: bool,
+    pub animation: AnimationSpeed,
+    /// Letters and numbers around the board.
+    pub coordinates: bool,
+    /// One of `THEMES`.
+    pub theme: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            free_cursor: false,
+            animation: AnimationSpeed::default(),
+            coordinates: true,
+            theme: THEMES[0].to_string(),
+        }
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::load_json(SETTINGS_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::save_json(SETTINGS_FILE, self)
+    }
+
+    fn lines(&self) -> Vec<String> {
+        vec![
+            format!("free cursor: {}", on_off(self.free_cursor)),
+            format!("flip animation: {:?}", self.animation).to_lowercase(),
+            format!("coordinates: {}", on_off(self.coordinates)),
+            format!("theme: {}", self.theme),
+        ]
+    }
+
+    /// Changes the setting on line `idx` of `lines`.
+    fn toggle(&mut self, idx: usize) {
+        match idx {
+            0 => self.free_cursor = !self.free_cursor,
+            1 => self.animation = self.animation.next(),
+            2 => self.coordinates = !self.coordinates,
+            3 => {
+                let idx = THEMES.iter().position(|t| *t == self.theme);
+                self.theme = THEMES[idx.map_or(0, |i| (i + 1) % THEMES.len())].to_string();
+            }
+            _ => (),
+        }
+    }
+}
+
+pub fn settings_screen<Ctx>(terminal: &mut TerminalHandler, _: &mut Ctx) -> Return {
+    let mut settings = Settings::load();
+    let mut screen = ListScreen::new(terminal);
+
+    loop {
+        let cursor = screen.list.cursor();
+        screen.list.set_items(settings.lines());
+        screen.list.move_cursor(cursor as i32);
+        screen.render(
+            terminal,
+            "Settings",
+            "up/down: select  enter: change  esc: save and back",
+        );
+        match terminal.getch() {
+            KeyCode::Esc => break,
+            KeyCode::Enter => settings.toggle(screen.list.cursor()),
+            k => screen.navigate(k),
+        }
+    }
+    if settings.theme == "custom" && !storage::data_path(THEME_FILE).is_ok_and(|path| path.exists())
+    {
+        // something to edit
+        _ = storage::save_json(THEME_FILE, &Theme::classic());
+    }
+    _ = settings.save();
+    screen.win.delete();
+    Return::None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_keep_their_defaults() {
+        let settings: Settings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings, Settings::default());
+
+        let mut settings = settings;
+        settings.toggle(0);
+        assert!(settings.free_cursor);
+        settings.toggle(1);
+        assert_eq!(settings.animation, AnimationSpeed::Fast);
+        assert_eq!(
+            settings.lines(),
+            vec![
+                "free cursor: on",
+                "flip animation: fast",
+                "coordinates: on",
+                "theme: classic"
+            ]
+        );
+        settings.toggle(3);
+        assert_eq!(settings.theme, "high-contrast");
+        settings.theme = "custom".to_string();
+        settings.toggle(3);
+        assert_eq!(settings.theme, "classic");
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(test)]
+thread_local! {
+    static TEST_DATA_DIR: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+#[cfg(test)]
+pub struct TempDataDir(PathBuf);
+
+/// Keeps what a test saves out of the real data directory. Only the calling
+/// thread sees the new directory, and it is removed when the guard drops.
+#[cfg(test)]
+pub fn temp_data_dir() -> TempDataDir {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    let dir = env::temp_dir().join(format!("othello-rs-test-{}-{}", std::process::id(), n));
+    TEST_DATA_DIR.set(Some(dir.clone()));
+    TempDataDir(dir)
+}
+
+#[cfg(test)]
+impl Drop for TempDataDir {
+    fn drop(&mut self) {
+        TEST_DATA_DIR.set(None);
+        _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// An environment variable, with an empty value taken as unset.
+fn var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Directory holding everything othello-rs keeps between runs.
+/// `OTHELLO_RS_DATA` overrides the platform default.
+pub fn data_dir() -> Option<PathBuf> {
+    #[cfg(test)]
+    if let Some(dir) = TEST_DATA_DIR.with_borrow(Clone::clone) {
+        return Some(dir);
+    }
+    if let Some(dir) = var("OTHELLO_RS_DATA") {
+        return Some(PathBuf::from(dir));
+    }
+    if cfg!(windows) {
+        return var("APPDATA").map(|dir| PathBuf::from(dir).join("othello-rs"));
+    }
+    if let Some(dir) = var("XDG_DATA_HOME") {
+        return Some(PathBuf::from(dir).join("othello-rs"));
+    }
+    var("HOME").map(|home| PathBuf::from(home).join(".local/share/othello-rs"))
+}
+
+pub fn data_path(name: &str) -> Result<PathBuf, String> {
+    let dir = data_dir().ok_or("unable to find a data directory")?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join(name))
+}
+
+/// Reads `name` from the data directory, falling back to the default
+/// when it is missing or unreadable.
+pub fn load_json<T: DeserializeOwned + Default>(name: &str) -> T {
+    match data_path(name).and_then(|p| fs::read_to_string(p).map_err(|e| e.to_string())) {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+        Err(_) => T::default(),
+    }
+}
+
+pub fn save_json<T: Serialize>(name: &str, value: &T) -> Result<(), String> {
+    let path = data_path(name)?;
+    let s = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    fs::write(path, s).map_err(|e| e.to_string())
+}
+
+pub fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Splits a unix timestamp into UTC `(year, month, day, hour, minute, second)`.
+pub fn civil_time(secs: u64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    let (hour, minute, second) = (
+        (rem / 3600) as u32,
+        (rem % 3600 / 60) as u32,
+        (rem % 60) as u32,
+    );
+
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day, hour, minute, second)
+}
@@ -1,6 +1,7 @@
 pub mod buffer;
 pub mod crossterm_handler;
 pub mod elements;
+pub mod recorder;
 pub mod terminal_window;
 pub mod window;
 
@@ -1,16 +1,79 @@
 use crate::termin::buffer::Cell;
 use crossterm::{
     cursor::MoveTo,
-    event::{read, Event, KeyCode},
+    event::{poll, read, Event, EventStream, KeyCode},
     queue,
     style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
 };
-use std::io::{self, Write};
+use futures_util::stream::{LocalBoxStream, StreamExt};
+#[cfg(test)]
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    thread,
+    time::Instant,
+};
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
 
 use super::window::WindowRef;
 
 pub struct CrosstermHandler<W: Write> {
     buffer: W,
+    #[cfg(test)]
+    input: Option<ScriptedInput>,
+}
+
+/// Events handed out in place of the terminal's, so tests can drive a
+/// screen. Reading waits for the next one to be pushed.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct ScriptedInput(Arc<Mutex<VecDeque<Event>>>);
+
+#[cfg(test)]
+impl ScriptedInput {
+    /// How long a read waits before the test is taken to be stuck.
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    pub fn key(&self, code: KeyCode) {
+        self.0.lock().unwrap().push_back(Event::Key(code.into()));
+    }
+
+    fn pop(&self) -> Option<Event> {
+        self.0.lock().unwrap().pop_front()
+    }
+
+    fn poll(&self, timeout: Duration) -> Option<Event> {
+        let start = Instant::now();
+        loop {
+            if let Some(e) = self.pop() {
+                return Some(e);
+            }
+            if start.elapsed() >= timeout {
+                return None;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn next(&self) -> Event {
+        self.poll(Self::TIMEOUT)
+            .expect("waited too long for scripted input")
+    }
+
+    fn stream(self) -> LocalBoxStream<'static, io::Result<Event>> {
+        futures_util::stream::unfold(self, |input| async move {
+            loop {
+                if let Some(e) = input.pop() {
+                    return Some((Ok(e), input));
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .boxed_local()
+    }
 }
 
 impl<W> Write for CrosstermHandler<W>
@@ -31,7 +94,18 @@ where
     W: Write,
 {
     pub fn new(buffer: W) -> Self {
-        CrosstermHandler { buffer }
+        CrosstermHandler {
+            buffer,
+            #[cfg(test)]
+            input: None,
+        }
+    }
+
+    /// Reads events from `input` instead of the terminal.
+    #[cfg(test)]
+    pub fn with_input(mut self, input: ScriptedInput) -> Self {
+        self.input = Some(input);
+        self
     }
 
     pub fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
@@ -87,6 +161,10 @@ where
     }
 
     pub fn event(&self) -> Event {
+        #[cfg(test)]
+        if let Some(input) = &self.input {
+            return input.next();
+        }
         read().unwrap()
     }
 
@@ -99,6 +177,28 @@ where
         }
     }
 
+    /// Like `event`, but gives up after `timeout` so callers can keep a timer running.
+    pub fn poll_event(&self, timeout: Duration) -> Option<Event> {
+        #[cfg(test)]
+        if let Some(input) = &self.input {
+            return input.poll(timeout);
+        }
+        if poll(timeout).unwrap_or(false) {
+            Some(self.event())
+        } else {
+            None
+        }
+    }
+
+    /// Events as a stream, for screens that wait on a socket at the same time.
+    pub fn event_stream(&self) -> LocalBoxStream<'static, io::Result<Event>> {
+        #[cfg(test)]
+        if let Some(input) = &self.input {
+            return input.clone().stream();
+        }
+        EventStream::new().boxed_local()
+    }
+
     pub fn flush(&mut self) -> io::Result<()> {
         self.buffer.flush()
     }
@@ -1,4 +1,4 @@
-use std::io::{stdout, Write};
+use std::io::Write;
 
 use copypasta::ClipboardProvider;
 use crossterm::{
@@ -65,8 +65,8 @@ impl InputWindow {
         self.update_rel_xy();
     }
 
-    pub fn show_cursor(&self) {
-        execute!(stdout(), cursor::Show).unwrap();
+    pub fn show_cursor<W: Write>(&self, out: &mut W) {
+        execute!(out, cursor::Show).unwrap();
     }
 
     pub fn get_text_clone(&self) -> String {
@@ -77,8 +77,8 @@ impl InputWindow {
         self.input_box.get_text()
     }
 
-    pub fn hide_cursor(&self) {
-        execute!(stdout(), cursor::Hide).unwrap();
+    pub fn hide_cursor<W: Write>(&self, out: &mut W) {
+        execute!(out, cursor::Hide).unwrap();
     }
 
     pub fn start_text(mut self, start_text: (u32, u32)) -> Self {
@@ -131,12 +131,10 @@ impl InputWindow {
             }
             Event::Key(k) => match k.code {
                 KeyCode::Esc => {
-                    self.hide_cursor();
                     self.input_win.delete();
                     return EventResult::Return(self.input_box.get_text().to_string());
                 }
                 KeyCode::Enter => {
-                    self.hide_cursor();
                     self.input_win.delete();
                     return EventResult::Return(self.input_box.get_text().to_string());
                 }
@@ -171,9 +169,9 @@ impl InputWindow {
         return EventResult::Continue;
     }
 
-    pub fn update_cursor(&self) {
+    pub fn update_cursor<W: Write>(&self, out: &mut W) {
         queue!(
-            stdout(),
+            out,
             cursor::MoveTo((self.abs_x + self.rel_x) as u16, (self.abs_y + self.rel_y) as u16)
         )
         .unwrap();
@@ -202,20 +200,23 @@ impl InputWindow {
 
     pub fn read_string<W: Write>(&mut self, handler: &mut CrosstermHandler<W>) -> String {
         self.set_abs_xy();
-        self.show_cursor();
+        self.show_cursor(handler);
         self.update_rel_xy();
-        self.update_cursor();
+        self.update_cursor(handler);
         handler.flush().unwrap();
 
         loop {
             match self.handle_event(handler.event()) {
-                EventResult::Return(s) => return s,
+                EventResult::Return(s) => {
+                    self.hide_cursor(handler);
+                    return s;
+                }
                 _ => (),
             }
             self.render();
             handler.draw_window(self.input_win()).unwrap();
 
-            self.update_cursor();
+            self.update_cursor(handler);
             handler.flush().unwrap();
         }
     }
@@ -0,0 +1,160 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::json;
+
+/// Passes everything through to `inner` and, when recording, tees it into an
+/// asciicast v2 file. Output is collected between flushes so that one redraw
+/// becomes one event, preceded by a resize event when the terminal changed
+/// size since the last one.
+pub struct Recorder<W: Write> {
+    inner: W,
+    cast: Option<BufWriter<File>>,
+    start: Instant,
+    pending: Vec<u8>,
+    size: (u16, u16),
+    terminal_size: fn() -> io::Result<(u16, u16)>,
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            cast: None,
+            start: Instant::now(),
+            pending: vec![],
+            size: (0, 0),
+            terminal_size: crossterm::terminal::size,
+        }
+    }
+
+    /// Starts a recording of a `width` x `height` terminal into `path`.
+    pub fn create(inner: W, path: &Path, (width, height): (u16, u16)) -> io::Result<Self> {
+        let mut cast = BufWriter::new(File::create(path)?);
+        let header = json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "timestamp": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            "env": { "TERM": std::env::var("TERM").unwrap_or_default() },
+        });
+        writeln!(cast, "{}", header)?;
+
+        let mut recorder = Self::new(inner);
+        recorder.cast = Some(cast);
+        recorder.size = (width, height);
+        Ok(recorder)
+    }
+
+    /// Writes the output collected so far as one event. A multi-byte
+    /// character split by a write is kept for the next event.
+    fn record(&mut self) -> io::Result<()> {
+        let cast = match &mut self.cast {
+            Some(cast) if !self.pending.is_empty() => cast,
+            _ => return Ok(()),
+        };
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(s) => s.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        let time = self.start.elapsed().as_secs_f64();
+        if let Ok(size) = (self.terminal_size)() {
+            if size != self.size {
+                self.size = size;
+                let event = json!([time, "r", format!("{}x{}", size.0, size.1)]);
+                writeln!(cast, "{}", event)?;
+            }
+        }
+        let data = String::from_utf8_lossy(&self.pending[..valid]);
+        let event = json!([time, "o", data]);
+        writeln!(cast, "{}", event)?;
+        cast.flush()?;
+        self.pending.drain(..valid);
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Recorder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if self.cast.is_some() {
+            self.pending.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        self.record()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tees_output_as_asciicast_events() {
+        let path = std::env::temp_dir().join(format!("othello-rs-{}.cast", std::process::id()));
+        let mut out = vec![];
+        {
+            let mut recorder = Recorder::create(&mut out, &path, (80, 24)).unwrap();
+            recorder.terminal_size = || Ok((80, 24));
+            recorder.write_all("black: 2 ".as_bytes()).unwrap();
+            recorder.write_all(&"é".as_bytes()[..1]).unwrap();
+            recorder.flush().unwrap();
+            recorder.write_all(&"é".as_bytes()[1..]).unwrap();
+            recorder.flush().unwrap();
+            recorder.flush().unwrap();
+        }
+        assert_eq!(String::from_utf8(out).unwrap(), "black: 2 é");
+
+        let cast = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<serde_json::Value> = cast
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 80);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "black: 2 ");
+        assert_eq!(lines[2][2], "é");
+    }
+
+    #[test]
+    fn records_a_resize_before_the_redraw() {
+        let path =
+            std::env::temp_dir().join(format!("othello-rs-resize-{}.cast", std::process::id()));
+        {
+            let mut recorder = Recorder::create(io::sink(), &path, (80, 24)).unwrap();
+            recorder.terminal_size = || Ok((80, 24));
+            recorder.write_all(b"a").unwrap();
+            recorder.flush().unwrap();
+            recorder.terminal_size = || Ok((100, 30));
+            recorder.write_all(b"b").unwrap();
+            recorder.flush().unwrap();
+        }
+
+        let cast = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let events: Vec<(String, String)> = cast
+            .lines()
+            .skip(1)
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .map(|e| (e[1].as_str().unwrap().into(), e[2].as_str().unwrap().into()))
+            .collect();
+        let event = |kind: &str, data: &str| (kind.to_string(), data.to_string());
+        assert_eq!(
+            events,
+            [event("o", "a"), event("r", "100x30"), event("o", "b")]
+        );
+    }
+}
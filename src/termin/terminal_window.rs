@@ -1,8 +1,13 @@
 use crossterm::event::{Event, KeyCode, KeyEvent};
-use std::io::{self, Stdout, Write};
+use futures_util::stream::LocalBoxStream;
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
 
 use super::{
     crossterm_handler::CrosstermHandler,
+    recorder::Recorder,
     window::{Window, WindowRef},
 };
 
@@ -11,7 +16,9 @@ pub struct Terminal<W: Write> {
     pub root: WindowRef,
 }
 
-pub type TerminalHandler = Terminal<Stdout>;
+/// The terminal every screen draws to. The output is boxed so tests can
+/// throw it away.
+pub type TerminalHandler = Terminal<Recorder<Box<dyn Write>>>;
 
 impl<W: Write> Terminal<W> {
     pub fn new(root: Window, handler: CrosstermHandler<W>) -> Terminal<W> {
@@ -33,6 +40,14 @@ impl<W: Write> Terminal<W> {
         self.handler.getch()
     }
 
+    pub fn poll_event(&self, timeout: Duration) -> Option<Event> {
+        self.handler.poll_event(timeout)
+    }
+
+    pub fn event_stream(&self) -> LocalBoxStream<'static, io::Result<Event>> {
+        self.handler.event_stream()
+    }
+
     pub fn render(&mut self) {
         match self
             .handler
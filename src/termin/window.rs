@@ -48,11 +48,13 @@ pub enum Position {
     Coord(u32, u32),
 }
 
-impl Window {
-    pub fn default() -> Self {
+impl Default for Window {
+    fn default() -> Self {
         Window::new(0, 0, 0, 0)
     }
+}
 
+impl Window {
     pub fn new(width: u32, height: u32, x: u32, y: u32) -> Self {
         Window {
             buffer: Buffer::empty(
@@ -455,6 +457,17 @@ impl WindowRef {
         self.inner().abs_pos()
     }
 
+    /// Where the screen cell `(column, row)` falls inside this window, if it does.
+    pub fn hit(&self, column: u16, row: u16) -> Option<(u32, u32)> {
+        let (top, left) = self.abs_pos();
+        let (x, y) = (column as u32, row as u32);
+        if x >= left && y >= top && x < left + self.width() && y < top + self.height() {
+            Some((x - left, y - top))
+        } else {
+            None
+        }
+    }
+
     pub fn extend_scroll_height(&mut self, dy: u32) {
         self.inner_mut().extend_scroll_height(dy);
     }
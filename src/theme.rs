@@ -0,0 +1,246 @@
+use crossterm::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::board::{Side, BLACK, WHITE},
+    settings::Settings,
+    storage,
+};
+
+/// Where the `custom` theme is read from.
+pub const THEME_FILE: &str = "theme.json";
+
+/// Names of the built-in themes, in the order the settings screen cycles them.
+pub const THEMES: [&str; 5] = [
+    "classic",
+    "high-contrast",
+    "colorblind-safe",
+    "monochrome",
+    "custom",
+];
+
+/// Every colour the game draws with. Colours are written the way crossterm
+/// reads them: `green`, `dark_grey`, `rgb_(80,220,120)`...
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    pub board: Color,
+    pub empty: Color,
+    pub black: Color,
+    pub white: Color,
+    /// Discs flipped by the last move.
+    pub black_tint: Color,
+    pub white_tint: Color,
+    /// What a disc turns through going from black to white.
+    pub flip_shades: [Color; 2],
+    pub legal_move: Color,
+    pub cursor: Color,
+    pub illegal_cursor: Color,
+    pub mark: Color,
+    pub label: Color,
+    /// The current move in move lists and replays.
+    pub highlight: Color,
+    pub my_chat: Color,
+    pub their_chat: Color,
+    /// Whether the opponent is connected, shown next to their name in the chat.
+    pub online: Color,
+    pub offline: Color,
+    /// Menu selections, dialogs and the other frames drawn around text.
+    pub frame: Color,
+    pub frame_text: Color,
+    /// Dialog boxes reporting an error or some news.
+    pub error: Color,
+    pub info: Color,
+    /// Discs are drawn as ● and ○ on empty squares instead of solid blocks.
+    pub glyphs: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl Theme {
+    pub fn classic() -> Self {
+        Self {
+            board: Color::Green,
+            empty: Color::Rgb {
+                r: 80,
+                g: 220,
+                b: 120,
+            },
+            black: Color::Black,
+            white: Color::White,
+            black_tint: Color::Rgb {
+                r: 50,
+                g: 50,
+                b: 70,
+            },
+            white_tint: Color::Rgb {
+                r: 205,
+                g: 205,
+                b: 225,
+            },
+            flip_shades: [Color::DarkGrey, Color::Grey],
+            legal_move: Color::Blue,
+            cursor: Color::Yellow,
+            illegal_cursor: Color::DarkRed,
+            mark: Color::Red,
+            label: Color::Black,
+            highlight: Color::Yellow,
+            my_chat: Color::Blue,
+            their_chat: Color::Red,
+            online: Color::Green,
+            offline: Color::Red,
+            frame: Color::Green,
+            frame_text: Color::Black,
+            error: Color::Red,
+            info: Color::Blue,
+            glyphs: false,
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            board: Color::DarkGreen,
+            empty: Color::DarkGrey,
+            black: Color::Black,
+            white: Color::White,
+            black_tint: Color::DarkBlue,
+            white_tint: Color::Cyan,
+            legal_move: Color::Cyan,
+            cursor: Color::Yellow,
+            illegal_cursor: Color::Red,
+            mark: Color::Magenta,
+            label: Color::White,
+            my_chat: Color::Cyan,
+            their_chat: Color::Yellow,
+            frame: Color::Yellow,
+            ..Self::classic()
+        }
+    }
+
+    /// Okabe-Ito colours, which stay apart for the common kinds of colour blindness.
+    pub fn colorblind_safe() -> Self {
+        let rgb = |r, g, b| Color::Rgb { r, g, b };
+        Self {
+            board: rgb(0, 114, 178),
+            empty: rgb(86, 180, 233),
+            legal_move: rgb(230, 159, 0),
+            cursor: rgb(240, 228, 66),
+            illegal_cursor: rgb(213, 94, 0),
+            mark: rgb(204, 121, 167),
+            label: Color::White,
+            highlight: rgb(240, 228, 66),
+            my_chat: rgb(0, 114, 178),
+            their_chat: rgb(213, 94, 0),
+            online: rgb(0, 158, 115),
+            offline: rgb(213, 94, 0),
+            frame: rgb(86, 180, 233),
+            error: rgb(213, 94, 0),
+            info: rgb(0, 114, 178),
+            ..Self::classic()
+        }
+    }
+
+    /// Greys only; the glyphs tell the sides apart.
+    pub fn monochrome() -> Self {
+        Self {
+            board: Color::Black,
+            empty: Color::DarkGrey,
+            black: Color::White,
+            white: Color::White,
+            black_tint: Color::Grey,
+            white_tint: Color::Grey,
+            flip_shades: [Color::Grey, Color::Grey],
+            legal_move: Color::Grey,
+            cursor: Color::White,
+            illegal_cursor: Color::Black,
+            mark: Color::White,
+            label: Color::White,
+            highlight: Color::White,
+            my_chat: Color::White,
+            their_chat: Color::Grey,
+            online: Color::White,
+            offline: Color::DarkGrey,
+            frame: Color::Grey,
+            frame_text: Color::Black,
+            error: Color::DarkGrey,
+            info: Color::DarkGrey,
+            glyphs: true,
+        }
+    }
+
+    /// A built-in theme, or `custom` read from `theme.json`.
+    pub fn by_name(name: &str) -> Self {
+        match name {
+            "high-contrast" => Self::high_contrast(),
+            "colorblind-safe" => Self::colorblind_safe(),
+            "monochrome" => Self::monochrome(),
+            "custom" => storage::load_json(THEME_FILE),
+            _ => Self::classic(),
+        }
+    }
+
+    /// The theme picked in the settings.
+    pub fn load() -> Self {
+        Self::by_name(&Settings::load().theme)
+    }
+
+    pub fn disc(&self, side: Side) -> Color {
+        match side {
+            WHITE => self.white,
+            BLACK => self.black,
+            _ => self.empty,
+        }
+    }
+
+    pub fn tint(&self, side: Side) -> Color {
+        match side {
+            WHITE => self.white_tint,
+            BLACK => self.black_tint,
+            _ => self.empty,
+        }
+    }
+}
+
+pub fn glyph(side: Side) -> &'static str {
+    if side == WHITE {
+        "○"
+    } else {
+        "●"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_round_trip_and_fill_gaps_from_classic() {
+        for name in THEMES.iter().filter(|name| **name != "custom") {
+            let theme = Theme::by_name(name);
+            let json = serde_json::to_string(&theme).unwrap();
+            assert_eq!(serde_json::from_str::<Theme>(&json).unwrap(), theme);
+        }
+
+        let theme: Theme = serde_json::from_str(
+            r#"{"board": "dark_blue", "empty": "rgb_(1,2,3)", "glyphs": true}"#,
+        )
+        .unwrap();
+        assert_eq!(theme.board, Color::DarkBlue);
+        assert_eq!(theme.empty, Color::Rgb { r: 1, g: 2, b: 3 });
+        assert_eq!(theme.cursor, Theme::classic().cursor);
+        assert!(theme.glyphs);
+    }
+
+    #[test]
+    fn discs_stand_out_from_the_board() {
+        for name in THEMES.iter().filter(|name| **name != "custom") {
+            let theme = Theme::by_name(name);
+            assert_ne!(theme.board, theme.black, "{}", name);
+            assert_ne!(theme.board, theme.white, "{}", name);
+        }
+    }
+}